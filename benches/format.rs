@@ -0,0 +1,79 @@
+/*
+jfmt
+Copyright (C) 2024 - Víctor Berga
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <http://www.gnu.org/licenses/>.
+*/
+
+//! Rendering benchmarks for large arrays and deeply nested documents. The
+//! documents are parsed once, outside of the measurements.
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use jfmt::options::Compact;
+use jfmt::{parse, Dialect, FormatOptions, Node};
+
+/// An array of `length` small objects.
+fn large_array(length: usize) -> String {
+    let items: Vec<String> = (0..length)
+        .map(|index| format!(r#"{{"id": {index}, "name": "item {index}", "tags": ["a", "b"]}}"#))
+        .collect();
+
+    format!("[{}]", items.join(", "))
+}
+
+/// `depth` arrays, each holding a number and the next one.
+fn deep_nesting(depth: usize) -> String {
+    format!("{}0{}", "[1, ".repeat(depth), "]".repeat(depth))
+}
+
+fn bench(c: &mut Criterion, name: &str, documents: &[(usize, String)]) {
+    let options = FormatOptions::default();
+    let mut group = c.benchmark_group(name);
+
+    for (size, json) in documents {
+        let value = parse(json, Dialect::Json).unwrap();
+
+        group.bench_with_input(BenchmarkId::new("pretty", size), &value, |b, value| {
+            b.iter(|| value.format_as_root(&options).unwrap())
+        });
+        group.bench_with_input(BenchmarkId::new("compact", size), &value, |b, value| {
+            b.iter(|| value.format_compact(Compact::Tight, &options).unwrap())
+        });
+        group.bench_with_input(BenchmarkId::new("canonical", size), &value, |b, value| {
+            b.iter(|| value.format_canonical().unwrap())
+        });
+    }
+    group.finish();
+}
+
+fn large_arrays(c: &mut Criterion) {
+    let documents: Vec<_> = [1_000, 10_000, 100_000]
+        .into_iter()
+        .map(|length| (length, large_array(length)))
+        .collect();
+
+    bench(c, "large_array", &documents);
+}
+
+fn deeply_nested(c: &mut Criterion) {
+    let documents: Vec<_> = [100, 1_000, 2_000]
+        .into_iter()
+        .map(|depth| (depth, deep_nesting(depth)))
+        .collect();
+
+    bench(c, "deep_nesting", &documents);
+}
+
+criterion_group!(benches, large_arrays, deeply_nested);
+criterion_main!(benches);
@@ -0,0 +1,349 @@
+/*
+jfmt
+Copyright (C) 2024 - Víctor Berga
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <http://www.gnu.org/licenses/>.
+*/
+
+//! Differences between two documents as values rather than as text: the
+//! values added, removed or changed, addressed by JSON Pointers.
+
+use crate::nodes::{escape_canonical, unescaped, Object, Value};
+use crate::pointer::Pointer;
+use std::borrow::Cow;
+use std::collections::HashMap;
+
+/// What two documents may differ in and still be the same.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct CompareOptions {
+    /// Objects with the same members are the same in any order.
+    pub ignore_key_order: bool,
+    /// Arrays with the same elements are the same in any order.
+    pub ignore_array_order: bool,
+}
+
+/// One difference between the old and the new document.
+#[derive(Debug, PartialEq)]
+pub enum Change<'v, 'a> {
+    /// The new document has a value at `pointer` the old one has not.
+    Added {
+        pointer: Pointer,
+        value: &'v Value<'a>,
+    },
+    /// The old document has a value at `pointer` the new one has not.
+    Removed {
+        pointer: Pointer,
+        value: &'v Value<'a>,
+    },
+    /// The value at `pointer` was replaced by another of a different kind,
+    /// or by another scalar.
+    Changed {
+        pointer: Pointer,
+        old: &'v Value<'a>,
+        new: &'v Value<'a>,
+    },
+    /// The object at `pointer` has the members of both documents in another
+    /// order. Names are unescaped.
+    Reordered {
+        pointer: Pointer,
+        old: Vec<String>,
+        new: Vec<String>,
+    },
+}
+
+impl Change<'_, '_> {
+    pub fn pointer(&self) -> &Pointer {
+        match self {
+            Change::Added { pointer, .. }
+            | Change::Removed { pointer, .. }
+            | Change::Changed { pointer, .. }
+            | Change::Reordered { pointer, .. } => pointer,
+        }
+    }
+}
+
+/// Lists what changed from `old` to `new`, parents before their children
+/// and members in the order of the old document.
+///
+/// Elements of arrays are compared by their index. When the order of arrays
+/// is ignored, each new element is matched with an equal old one, and those
+/// with no match are reported as removed or added at their own index.
+pub fn compare<'v, 'a>(
+    old: &'v Value<'a>,
+    new: &'v Value<'a>,
+    options: &CompareOptions,
+) -> Vec<Change<'v, 'a>> {
+    let mut changes = Vec::new();
+    walk(Pointer::root(), old, new, options, &mut changes);
+
+    changes
+}
+
+fn walk<'v, 'a>(
+    pointer: Pointer,
+    old: &'v Value<'a>,
+    new: &'v Value<'a>,
+    options: &CompareOptions,
+    changes: &mut Vec<Change<'v, 'a>>,
+) {
+    match (old, new) {
+        (Value::Object(a), Value::Object(b)) => {
+            let ((a_names, a_values), (b_names, b_values)) = (members(a), members(b));
+
+            if !options.ignore_key_order {
+                let common = |names: &[Cow<str>], others: &Members| -> Vec<String> {
+                    let others = |name: &&Cow<str>| others.contains_key(*name);
+                    names
+                        .iter()
+                        .filter(others)
+                        .map(|name| name.to_string())
+                        .collect()
+                };
+                let (old_order, new_order) =
+                    (common(&a_names, &b_values), common(&b_names, &a_values));
+                if old_order != new_order {
+                    changes.push(Change::Reordered {
+                        pointer: pointer.clone(),
+                        old: old_order,
+                        new: new_order,
+                    });
+                }
+            }
+
+            for name in &a_names {
+                let value = a_values[name];
+                match b_values.get(name) {
+                    Some(other) => walk(pointer.child(name), value, other, options, changes),
+                    None => changes.push(Change::Removed {
+                        pointer: pointer.child(name),
+                        value,
+                    }),
+                }
+            }
+            for name in b_names.iter().filter(|name| !a_values.contains_key(*name)) {
+                changes.push(Change::Added {
+                    pointer: pointer.child(name),
+                    value: b_values[name],
+                });
+            }
+        }
+        (Value::Array(a), Value::Array(b)) if options.ignore_array_order => {
+            // New elements by key, the first one last so it is matched first.
+            let mut unmatched: HashMap<String, Vec<usize>> = HashMap::new();
+            for (index, value) in b.values.iter().enumerate().rev() {
+                unmatched
+                    .entry(key(value, options))
+                    .or_default()
+                    .push(index);
+            }
+
+            let mut matched = vec![false; b.values.len()];
+            let mut removed = Vec::new();
+            for (index, value) in a.values.iter().enumerate() {
+                match unmatched.get_mut(&key(value, options)).and_then(Vec::pop) {
+                    Some(other) => matched[other] = true,
+                    None => removed.push(index),
+                }
+            }
+
+            for index in removed {
+                changes.push(Change::Removed {
+                    pointer: pointer.child(&index.to_string()),
+                    value: &a.values[index],
+                });
+            }
+            for (index, value) in b.values.iter().enumerate() {
+                if !matched[index] {
+                    changes.push(Change::Added {
+                        pointer: pointer.child(&index.to_string()),
+                        value,
+                    });
+                }
+            }
+        }
+        (Value::Array(a), Value::Array(b)) => {
+            for (index, (value, other)) in a.values.iter().zip(&b.values).enumerate() {
+                walk(
+                    pointer.child(&index.to_string()),
+                    value,
+                    other,
+                    options,
+                    changes,
+                );
+            }
+
+            let common = b.values.len().min(a.values.len());
+            for (index, value) in a.values.iter().enumerate().skip(common) {
+                changes.push(Change::Removed {
+                    pointer: pointer.child(&index.to_string()),
+                    value,
+                });
+            }
+            for (index, value) in b.values.iter().enumerate().skip(common) {
+                changes.push(Change::Added {
+                    pointer: pointer.child(&index.to_string()),
+                    value,
+                });
+            }
+        }
+        _ if !old.equals(new) => changes.push(Change::Changed { pointer, old, new }),
+        _ => {}
+    }
+}
+
+/// Values by unescaped member name, keeping the last member of each name.
+type Members<'v, 'a> = HashMap<Cow<'v, str>, &'v Value<'a>>;
+
+/// The unescaped member names in the order they are first written, and the
+/// values they name.
+fn members<'v, 'a>(object: &'v Object<'a>) -> (Vec<Cow<'v, str>>, Members<'v, 'a>) {
+    let mut names = Vec::with_capacity(object.members.len());
+    let mut values = HashMap::with_capacity(object.members.len());
+    for (name, value) in &object.members {
+        let name = unescaped(name);
+        if values.insert(name.clone(), value).is_none() {
+            names.push(name);
+        }
+    }
+
+    (names, values)
+}
+
+/// Spells a value so that two values have the same key exactly when
+/// comparing them finds no changes.
+fn key(value: &Value, options: &CompareOptions) -> String {
+    match value {
+        Value::Object(object) => {
+            let (names, values) = members(object);
+            let mut keys: Vec<String> = names
+                .iter()
+                .map(|name| format!("{}:{}", escape_canonical(name), key(values[name], options)))
+                .collect();
+            if options.ignore_key_order {
+                keys.sort_unstable();
+            }
+
+            format!("{{{}}}", keys.join(","))
+        }
+        Value::Array(array) => {
+            let mut keys: Vec<String> = array
+                .values
+                .iter()
+                .map(|value| key(value, options))
+                .collect();
+            if options.ignore_array_order {
+                keys.sort_unstable();
+            }
+
+            format!("[{}]", keys.join(","))
+        }
+        Value::String(string) => escape_canonical(&unescaped(&string.value)),
+        Value::Number(number) => number.exact(),
+        Value::Bool(boolean) => boolean.value.to_string(),
+        Value::Null(_) => "null".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod compare_tests {
+    use super::*;
+    use crate::options::Dialect;
+    use crate::parser::parse;
+
+    /// Changes as `+ pointer`, `- pointer`, `~ pointer` or `^ pointer` for a
+    /// new order.
+    fn changes(old: &str, new: &str, options: CompareOptions) -> Vec<String> {
+        let (old, new) = (
+            parse(old, Dialect::Json5).unwrap(),
+            parse(new, Dialect::Json5).unwrap(),
+        );
+
+        compare(&old, &new, &options)
+            .iter()
+            .map(|change| {
+                let marker = match change {
+                    Change::Added { .. } => '+',
+                    Change::Removed { .. } => '-',
+                    Change::Changed { .. } => '~',
+                    Change::Reordered { .. } => '^',
+                };
+                format!("{marker} {}", change.pointer())
+            })
+            .collect()
+    }
+
+    #[test]
+    fn it_finds_added_removed_and_changed_values() {
+        let old = "{a: 1, b: {c: [1, 2, 3], 'd/e': true}, f: 'x'}";
+        let new = "{a: 1.0, b: {c: [1, 5], 'd/e': null}, f: ['x'], g: 0}";
+
+        assert_eq!(
+            vec!["~ /b/c/1", "- /b/c/2", "~ /b/d~1e", "~ /f", "+ /g"],
+            changes(old, new, CompareOptions::default())
+        );
+        assert_eq!(vec!["~ "], changes("1", "'1'", CompareOptions::default()));
+        assert!(changes(old, old, CompareOptions::default()).is_empty());
+        assert_eq!(
+            vec!["~ /0"],
+            changes(
+                "[12345678901234567891]",
+                "[12345678901234567892]",
+                CompareOptions::default()
+            )
+        );
+    }
+
+    #[test]
+    fn it_reports_the_order_of_keys_unless_ignored() {
+        let (old, new) = ("{a: 1, b: {x: 1, y: 2}}", "{b: {y: 2, x: 1, z: 3}, a: 1}");
+        let ignored = CompareOptions {
+            ignore_key_order: true,
+            ..CompareOptions::default()
+        };
+
+        assert_eq!(
+            vec!["^ ", "^ /b", "+ /b/z"],
+            changes(old, new, CompareOptions::default())
+        );
+        assert_eq!(vec!["+ /b/z"], changes(old, new, ignored));
+    }
+
+    #[test]
+    fn it_matches_elements_in_any_order_when_asked() {
+        let (old, new) = ("[1, {a: [2, 3]}, 4, 4]", "[{a: [3, 2]}, 4, 5, 1]");
+        let ignored = CompareOptions {
+            ignore_array_order: true,
+            ..CompareOptions::default()
+        };
+
+        assert_eq!(vec!["- /3", "+ /2"], changes(old, new, ignored));
+        assert_eq!(4, changes(old, new, CompareOptions::default()).len());
+    }
+
+    #[test]
+    fn it_compares_large_documents_quickly() {
+        let ignored = CompareOptions {
+            ignore_key_order: true,
+            ignore_array_order: true,
+        };
+        let elements: Vec<String> = (0..20_000).map(|n| format!("{{n: {n}}}")).collect();
+        let members: Vec<String> = (0..50_000).map(|n| format!("k{n}: {n}")).collect();
+        let reversed = |items: &[String]| items.iter().rev().cloned().collect::<Vec<_>>();
+
+        let (old, new) = (elements.join(","), reversed(&elements).join(","));
+        assert!(changes(&format!("[{old}]"), &format!("[{new}]"), ignored).is_empty());
+        let (old, new) = (members.join(","), reversed(&members).join(","));
+        assert!(changes(&format!("{{{old}}}"), &format!("{{{new}}}"), ignored).is_empty());
+    }
+}
@@ -0,0 +1,469 @@
+/*
+jfmt
+Copyright (C) 2024 - Víctor Berga
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <http://www.gnu.org/licenses/>.
+*/
+
+use jfmt::compare::Change;
+use jfmt::nodes::escape_canonical;
+use jfmt::options::{Compact, FormatOptions};
+use jfmt::theme::Style;
+use jfmt::{FormatError, Value};
+use std::ops::Range;
+
+/// Lines of context printed around each change.
+const CONTEXT: usize = 3;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Edit {
+    Equal,
+    Delete,
+    Insert,
+}
+
+/// Styles used for the parts of a diff. All of them are plain when the
+/// output is not colored.
+#[derive(Default)]
+pub struct DiffStyle {
+    header: Style,
+    hunk: Style,
+    deleted: Style,
+    inserted: Style,
+}
+
+impl DiffStyle {
+    pub fn colored() -> DiffStyle {
+        DiffStyle {
+            header: Style::parse("bold").unwrap(),
+            hunk: Style::parse("cyan").unwrap(),
+            deleted: Style::parse("red").unwrap(),
+            inserted: Style::parse("green").unwrap(),
+        }
+    }
+}
+
+/// Unified diff that turns `old` into `new`, or an empty string when they
+/// are equal.
+pub fn unified(name: &str, old: &str, new: &str, style: &DiffStyle) -> String {
+    let old: Vec<&str> = old.split_inclusive('\n').collect();
+    let new: Vec<&str> = new.split_inclusive('\n').collect();
+
+    let mut edits = Vec::with_capacity(old.len().max(new.len()));
+    let mut frontiers = Frontiers::default();
+    conquer(
+        &old,
+        0..old.len(),
+        &new,
+        0..new.len(),
+        &mut frontiers,
+        &mut edits,
+    );
+    deletions_first(&mut edits);
+
+    if edits.iter().all(|edit| *edit == Edit::Equal) {
+        return String::new();
+    }
+
+    let mut diff = style.header.paint(&format!("--- {name}\n+++ {name}\n"));
+    for hunk in hunks(&edits) {
+        write_hunk(&mut diff, &edits, hunk, (&old, &new), style);
+    }
+
+    diff
+}
+
+/// Lists changes found by comparing two documents, one per line: `+` for an
+/// added value, `-` for a removed one and `~` for a value replaced or an
+/// object reordered. Values are printed compact, read with the options of
+/// the document they come from.
+pub fn changes(
+    changes: &[Change],
+    (old_options, new_options): (&FormatOptions, &FormatOptions),
+    style: &DiffStyle,
+) -> Result<String, FormatError> {
+    let compact = |options: &FormatOptions| FormatOptions {
+        compact: Some(Compact::Spaced),
+        ..options.clone()
+    };
+    let (old_options, new_options) = (compact(old_options), compact(new_options));
+    let old_value = |value: &Value| jfmt::format_value(value.clone(), &old_options);
+    let new_value = |value: &Value| jfmt::format_value(value.clone(), &new_options);
+    let keys = |names: &[String]| {
+        let names: Vec<_> = names.iter().map(|name| escape_canonical(name)).collect();
+        format!("[{}]", names.join(", "))
+    };
+
+    let mut listed = String::new();
+    for change in changes {
+        let pointer = match change.pointer() {
+            pointer if pointer.is_root() => "(root)".to_string(),
+            pointer => pointer.to_string(),
+        };
+        let line = match change {
+            Change::Added { value: new, .. } => style
+                .inserted
+                .paint(&format!("+ {pointer}: {}", new_value(new)?)),
+            Change::Removed { value: old, .. } => style
+                .deleted
+                .paint(&format!("- {pointer}: {}", old_value(old)?)),
+            Change::Changed { old, new, .. } => format!(
+                "~ {pointer}: {} \u{2192} {}",
+                style.deleted.paint(&old_value(old)?),
+                style.inserted.paint(&new_value(new)?)
+            ),
+            Change::Reordered { old, new, .. } => format!(
+                "~ {pointer}: keys {} \u{2192} {}",
+                style.deleted.paint(&keys(old)),
+                style.inserted.paint(&keys(new))
+            ),
+        };
+        listed.push_str(&line);
+        listed.push('\n');
+    }
+
+    Ok(listed)
+}
+
+/// Lists the deletions of each run of changes before its insertions, which
+/// is how diffs are usually read.
+fn deletions_first(edits: &mut [Edit]) {
+    for run in edits.split_mut(|edit| *edit == Edit::Equal) {
+        run.sort_by_key(|edit| *edit == Edit::Insert);
+    }
+}
+
+/// Groups changes closer than twice the context into ranges of edits.
+fn hunks(edits: &[Edit]) -> Vec<Range<usize>> {
+    let mut hunks: Vec<Range<usize>> = Vec::new();
+
+    for (index, edit) in edits.iter().enumerate() {
+        if *edit == Edit::Equal {
+            continue;
+        }
+
+        let start = index.saturating_sub(CONTEXT);
+        let end = (index + 1 + CONTEXT).min(edits.len());
+        match hunks.last_mut() {
+            Some(last) if start <= last.end => last.end = end,
+            _ => hunks.push(start..end),
+        }
+    }
+
+    hunks
+}
+
+fn write_hunk(
+    diff: &mut String,
+    edits: &[Edit],
+    hunk: Range<usize>,
+    (old, new): (&[&str], &[&str]),
+    style: &DiffStyle,
+) {
+    let count = |skipped: Edit, edits: &[Edit]| edits.iter().filter(|e| **e != skipped).count();
+    let old_start = count(Edit::Insert, &edits[..hunk.start]);
+    let new_start = count(Edit::Delete, &edits[..hunk.start]);
+    let old_count = count(Edit::Insert, &edits[hunk.clone()]);
+    let new_count = count(Edit::Delete, &edits[hunk.clone()]);
+
+    // An empty range is numbered after the line that precedes it.
+    let position = |start: usize, count: usize| {
+        let first = if count == 0 { start } else { start + 1 };
+        format!("{first},{count}")
+    };
+    diff.push_str(&style.hunk.paint(&format!(
+        "@@ -{} +{} @@",
+        position(old_start, old_count),
+        position(new_start, new_count)
+    )));
+    diff.push('\n');
+
+    let (mut old_index, mut new_index) = (old_start, new_start);
+    for edit in &edits[hunk] {
+        let (marker, line, line_style) = match edit {
+            Edit::Equal => {
+                old_index += 1;
+                new_index += 1;
+                (' ', old[old_index - 1], &Style::default())
+            }
+            Edit::Delete => {
+                old_index += 1;
+                ('-', old[old_index - 1], &style.deleted)
+            }
+            Edit::Insert => {
+                new_index += 1;
+                ('+', new[new_index - 1], &style.inserted)
+            }
+        };
+
+        let text = line.strip_suffix('\n').unwrap_or(line);
+        diff.push_str(&line_style.paint(&format!("{marker}{text}")));
+        diff.push('\n');
+        if !line.ends_with('\n') {
+            diff.push_str("\\ No newline at end of file\n");
+        }
+    }
+}
+
+/// Furthest reaching paths of the forward and backward searches, indexed by
+/// diagonal. They are reused across the recursion to save allocations.
+#[derive(Default)]
+struct Frontiers {
+    forward: Vec<usize>,
+    backward: Vec<usize>,
+}
+
+/// Myers' linear space diff: strips the common ends, splits the rest at a
+/// middle snake and recurses into both halves.
+fn conquer(
+    old: &[&str],
+    mut old_range: Range<usize>,
+    new: &[&str],
+    mut new_range: Range<usize>,
+    frontiers: &mut Frontiers,
+    edits: &mut Vec<Edit>,
+) {
+    let prefix = old[old_range.clone()]
+        .iter()
+        .zip(&new[new_range.clone()])
+        .take_while(|(a, b)| a == b)
+        .count();
+    edits.extend(std::iter::repeat_n(Edit::Equal, prefix));
+    old_range.start += prefix;
+    new_range.start += prefix;
+
+    let suffix = old[old_range.clone()]
+        .iter()
+        .rev()
+        .zip(new[new_range.clone()].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    old_range.end -= suffix;
+    new_range.end -= suffix;
+
+    if old_range.is_empty() || new_range.is_empty() {
+        edits.extend(std::iter::repeat_n(Edit::Delete, old_range.len()));
+        edits.extend(std::iter::repeat_n(Edit::Insert, new_range.len()));
+    } else {
+        let (x, y) = middle_snake(old, old_range.clone(), new, new_range.clone(), frontiers);
+        conquer(
+            old,
+            old_range.start..x,
+            new,
+            new_range.start..y,
+            frontiers,
+            edits,
+        );
+        conquer(
+            old,
+            x..old_range.end,
+            new,
+            y..new_range.end,
+            frontiers,
+            edits,
+        );
+    }
+
+    edits.extend(std::iter::repeat_n(Edit::Equal, suffix));
+}
+
+/// Finds a point on an optimal path half way between both ends, searching
+/// forwards from the start and backwards from the end at the same time.
+/// Both ranges must be non-empty and differ in their first and last lines.
+fn middle_snake(
+    old: &[&str],
+    old_range: Range<usize>,
+    new: &[&str],
+    new_range: Range<usize>,
+    frontiers: &mut Frontiers,
+) -> (usize, usize) {
+    let (n, m) = (old_range.len() as isize, new_range.len() as isize);
+    let old = &old[old_range.clone()];
+    let new = &new[new_range.clone()];
+    let delta = n - m;
+    let odd = delta % 2 != 0;
+
+    let limit = (n + m + 1) / 2 + 1;
+    let offset = limit + 1;
+    let size = (2 * offset + 1) as usize;
+    let Frontiers { forward, backward } = frontiers;
+    forward.clear();
+    forward.resize(size, 0);
+    backward.clear();
+    backward.resize(size, 0);
+    let at = |k: isize| (k + offset) as usize;
+
+    for d in 0..=limit {
+        for k in (-d..=d).step_by(2) {
+            let mut x = if k == -d || (k != d && forward[at(k - 1)] < forward[at(k + 1)]) {
+                forward[at(k + 1)]
+            } else {
+                forward[at(k - 1)] + 1
+            } as isize;
+            let (start_x, mut y) = (x, x - k);
+            let start_y = y;
+            while x < n && y < m && old[x as usize] == new[y as usize] {
+                x += 1;
+                y += 1;
+            }
+            forward[at(k)] = x as usize;
+
+            let reverse = delta - k;
+            if odd
+                && (-(d - 1)..=d - 1).contains(&reverse)
+                && x + backward[at(reverse)] as isize >= n
+            {
+                return (
+                    old_range.start + start_x as usize,
+                    new_range.start + start_y as usize,
+                );
+            }
+        }
+
+        for k in (-d..=d).step_by(2) {
+            let mut x = if k == -d || (k != d && backward[at(k - 1)] < backward[at(k + 1)]) {
+                backward[at(k + 1)]
+            } else {
+                backward[at(k - 1)] + 1
+            } as isize;
+            let mut y = x - k;
+            while x < n && y < m && old[(n - x - 1) as usize] == new[(m - y - 1) as usize] {
+                x += 1;
+                y += 1;
+            }
+            backward[at(k)] = x as usize;
+
+            let reverse = delta - k;
+            if !odd && (-d..=d).contains(&reverse) && x + forward[at(reverse)] as isize >= n {
+                return (
+                    old_range.start + (n - x) as usize,
+                    new_range.start + (m - y) as usize,
+                );
+            }
+        }
+    }
+
+    unreachable!("the forward and backward searches always meet")
+}
+
+#[cfg(test)]
+mod diff_tests {
+    use super::*;
+    use jfmt::compare::{compare, CompareOptions};
+    use jfmt::Dialect;
+
+    fn plain(old: &str, new: &str) -> String {
+        unified("data.json", old, new, &DiffStyle::default())
+    }
+
+    #[test]
+    fn it_lists_the_changes_between_documents() {
+        let old = jfmt::parse("{\"a\": [1, 2], \"b\": 1, \"c\": true}", Dialect::Json).unwrap();
+        let new = jfmt::parse(
+            "{\"c\": true, \"a\": [1], \"b\": {\"x\": 1}}",
+            Dialect::Json,
+        )
+        .unwrap();
+        let listed = changes(
+            &compare(&old, &new, &CompareOptions::default()),
+            (&FormatOptions::default(), &FormatOptions::default()),
+            &DiffStyle::default(),
+        )
+        .unwrap();
+
+        assert_eq!(
+            "~ (root): keys [\"a\", \"b\", \"c\"] \u{2192} [\"c\", \"a\", \"b\"]\n- /a/1: 2\n~ /b: 1 \u{2192} {\"x\": 1}\n",
+            listed
+        );
+
+        let old = jfmt::parse("{\"\\u0001\u{e9}\": 1, \"b\": 2}", Dialect::Json).unwrap();
+        let new = jfmt::parse("{\"b\": 2, \"\\u0001\u{e9}\": 1}", Dialect::Json).unwrap();
+        let listed = changes(
+            &compare(&old, &new, &CompareOptions::default()),
+            (&FormatOptions::default(), &FormatOptions::default()),
+            &DiffStyle::default(),
+        )
+        .unwrap();
+
+        assert_eq!(
+            "~ (root): keys [\"\\u0001\u{e9}\", \"b\"] \u{2192} [\"b\", \"\\u0001\u{e9}\"]\n",
+            listed
+        );
+    }
+
+    #[test]
+    fn it_prints_nothing_for_equal_texts() {
+        assert_eq!("", plain("{}\n", "{}\n"));
+    }
+
+    #[test]
+    fn it_prints_a_unified_diff() {
+        let old = "{\n\"a\": 1,\n  \"b\": 2\n}\n";
+        let new = "{\n  \"a\": 1,\n  \"b\": 2\n}\n";
+        let expected = "--- data.json\n+++ data.json\n@@ -1,4 +1,4 @@\n {\n-\"a\": 1,\n+  \"a\": 1,\n   \"b\": 2\n }\n";
+
+        assert_eq!(expected, plain(old, new));
+    }
+
+    #[test]
+    fn it_splits_distant_changes_into_hunks() {
+        let old = "a\nb\nc\nd\ne\nf\ng\nh\ni\nj\n";
+        let new = "A\nb\nc\nd\ne\nf\ng\nh\ni\nJ\n";
+        let expected = "--- data.json\n+++ data.json\n@@ -1,4 +1,4 @@\n-a\n+A\n b\n c\n d\n@@ -7,4 +7,4 @@\n g\n h\n i\n-j\n+J\n";
+
+        assert_eq!(expected, plain(old, new));
+    }
+
+    #[test]
+    fn it_lists_deletions_before_insertions() {
+        let expected = "--- data.json\n+++ data.json\n@@ -1,2 +1,1 @@\n-{\"a\":1,\n-\"b\":2}\n+{\"a\": 1, \"b\": 2}\n";
+
+        assert_eq!(
+            expected,
+            plain("{\"a\":1,\n\"b\":2}\n", "{\"a\": 1, \"b\": 2}\n")
+        );
+    }
+
+    #[test]
+    fn it_marks_a_missing_final_newline() {
+        let expected = "--- data.json\n+++ data.json\n@@ -1,1 +1,1 @@\n-{ }\n\\ No newline at end of file\n+{}\n";
+
+        assert_eq!(expected, plain("{ }", "{}\n"));
+    }
+
+    #[test]
+    fn it_numbers_empty_ranges_after_the_previous_line() {
+        let expected = "--- data.json\n+++ data.json\n@@ -0,0 +1,1 @@\n+[]\n";
+
+        assert_eq!(expected, plain("", "[]\n"));
+    }
+
+    #[test]
+    fn it_finds_a_shortest_edit_script() {
+        let old = ["a", "b", "c", "a", "b", "b", "a"];
+        let new = ["c", "b", "a", "b", "a", "c"];
+        let mut edits = Vec::new();
+        conquer(
+            &old,
+            0..7,
+            &new,
+            0..6,
+            &mut Frontiers::default(),
+            &mut edits,
+        );
+        let changes = edits.iter().filter(|edit| **edit != Edit::Equal).count();
+
+        assert_eq!(5, changes);
+    }
+}
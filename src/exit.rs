@@ -0,0 +1,173 @@
+/*
+jfmt
+Copyright (C) 2024 - Víctor Berga
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <http://www.gnu.org/licenses/>.
+*/
+
+use crate::input::InputError;
+use jfmt::filter::FilterError;
+use jfmt::pointer::PointerError;
+use jfmt::stream;
+use std::fmt;
+use std::io;
+use std::process::ExitCode;
+
+/// Exit statuses reported by jfmt, so scripts can tell failures apart.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Status {
+    Success = 0,
+    /// The input is not valid JSON.
+    InvalidJson = 1,
+    /// An input could not be read or the output could not be written.
+    Io = 2,
+    /// `--check` found inputs that are not formatted.
+    Unformatted = 3,
+    /// The input is valid but cannot be printed with the given options.
+    Unformattable = 4,
+    /// `--pointer` addresses no value of the input, or `--filter` cannot be
+    /// applied to it.
+    Inapplicable = 5,
+    /// `jfmt diff` found the documents differ.
+    Different = 6,
+    /// The command line arguments are wrong.
+    Usage = 64,
+}
+
+impl From<Status> for ExitCode {
+    fn from(status: Status) -> Self {
+        ExitCode::from(status as u8)
+    }
+}
+
+/// An error that ends a run of jfmt.
+#[derive(Debug)]
+pub enum Failure {
+    Format(jfmt::Error),
+    /// An error in one of the documents of an input that holds many.
+    Record {
+        location: String,
+        error: jfmt::Error,
+    },
+    /// An error in an input formatted as it was read.
+    Stream {
+        path: String,
+        error: stream::Error,
+    },
+    /// `--pointer` addresses no value of the input at `path`.
+    Pointer {
+        path: String,
+        error: PointerError,
+    },
+    /// `--filter` cannot be applied to the input at `path`.
+    Filter {
+        path: String,
+        error: FilterError,
+    },
+    Input(InputError),
+    Io(io::Error),
+    /// The result could not be written to the file at `path`.
+    Write {
+        path: String,
+        error: io::Error,
+    },
+    /// The arguments are well formed but cannot be used together.
+    Usage(String),
+    /// The input at this path differs from its formatted output.
+    Unformatted(String),
+    /// The documents compared by `jfmt diff` differ.
+    Different {
+        old: String,
+        new: String,
+    },
+}
+
+impl Failure {
+    pub fn status(&self) -> Status {
+        match self {
+            Failure::Format(error) | Failure::Record { error, .. } => match error {
+                jfmt::Error::Parse(_) => Status::InvalidJson,
+                jfmt::Error::Format(_) => Status::Unformattable,
+            },
+            Failure::Stream { error, .. } => match error {
+                stream::Error::Syntax { .. } => Status::InvalidJson,
+                stream::Error::Format(_) => Status::Unformattable,
+                stream::Error::Read(_) | stream::Error::Write(_) | stream::Error::Encoding(_) => {
+                    Status::Io
+                }
+            },
+            Failure::Pointer { .. } | Failure::Filter { .. } => Status::Inapplicable,
+            Failure::Input(_) | Failure::Io(_) | Failure::Write { .. } => Status::Io,
+            Failure::Usage(_) => Status::Usage,
+            Failure::Unformatted(_) => Status::Unformatted,
+            Failure::Different { .. } => Status::Different,
+        }
+    }
+}
+
+impl fmt::Display for Failure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Failure::Format(error) => write!(f, "{error}"),
+            Failure::Record { location, error } => write!(f, "{location}: {error}"),
+            Failure::Stream { path, error } => write!(f, "{path}: {error}"),
+            Failure::Pointer { path, error } => write!(f, "{path}: {error}"),
+            Failure::Filter { path, error } => write!(f, "{path}: {error}"),
+            Failure::Input(error) => write!(f, "{error}"),
+            Failure::Io(error) => write!(f, "{error}"),
+            Failure::Write { path, error } => write!(f, "{path}: {error}"),
+            Failure::Usage(message) => write!(f, "{message}"),
+            Failure::Unformatted(path) => write!(f, "{path} is not formatted"),
+            Failure::Different { old, new } => write!(f, "{old} and {new} differ"),
+        }
+    }
+}
+
+impl From<jfmt::Error> for Failure {
+    fn from(error: jfmt::Error) -> Self {
+        Failure::Format(error)
+    }
+}
+
+impl From<InputError> for Failure {
+    fn from(error: InputError) -> Self {
+        Failure::Input(error)
+    }
+}
+
+impl From<io::Error> for Failure {
+    fn from(error: io::Error) -> Self {
+        Failure::Io(error)
+    }
+}
+
+#[cfg(test)]
+mod exit_tests {
+    use super::*;
+    use jfmt::FormatOptions;
+
+    #[test]
+    fn it_reports_invalid_json() {
+        let error = jfmt::format_with("[1,", &FormatOptions::default()).unwrap_err();
+
+        assert_eq!(Status::InvalidJson, Failure::from(error).status());
+    }
+
+    #[test]
+    fn it_reports_io_errors() {
+        let error = io::Error::from(io::ErrorKind::NotFound);
+
+        assert_eq!(Status::Io, Failure::from(error).status());
+    }
+}
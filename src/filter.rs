@@ -0,0 +1,849 @@
+/*
+jfmt
+Copyright (C) 2024 - Víctor Berga
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <http://www.gnu.org/licenses/>.
+*/
+
+//! Filters written in a subset of the jq language, such as
+//! `.items[] | select(.status == "failed") | {id, name: .metadata.name}`.
+//!
+//! A filter turns its input into any number of outputs. Paths (`.a.b`,
+//! `.[0]`, `.["a"]`), iteration (`.[]`), pipes, commas, comparisons, `and`,
+//! `or`, array and object construction and the functions `select`, `map`,
+//! `del`, `keys`, `length` and `not` behave as they do in jq.
+
+use crate::nodes::{escape_char, unescape, unescaped, Array, Boolean, Null, Number, Object, Span};
+use crate::nodes::{String, Value};
+use pest::error::{Error, ErrorVariant};
+use pest::iterators::Pair;
+use pest::Parser;
+use pest_derive::Parser;
+use std::borrow::Cow;
+use std::cmp::Ordering;
+use std::fmt;
+
+/// A parsed filter.
+#[derive(Clone, Debug)]
+pub struct Filter {
+    expression: Expression,
+}
+
+#[derive(Debug)]
+pub enum FilterError {
+    /// The filter is not written in the supported language.
+    Syntax(Box<Error<Rule>>),
+    /// The filter does something its input does not allow, such as taking a
+    /// member of a number.
+    Apply(std::string::String),
+}
+
+#[derive(Parser)]
+#[grammar = "filter.pest"]
+struct FilterParser;
+
+#[derive(Clone, Debug)]
+enum Expression {
+    Identity,
+    /// A member of each output of the target, by its unescaped name.
+    Field(Box<Expression>, std::string::String),
+    /// An element or member of each output of the target, chosen by each
+    /// output of the index applied to the input.
+    Index(Box<Expression>, Box<Expression>),
+    Iterate(Box<Expression>),
+    Literal(Value<'static>),
+    Pipe(Box<Expression>, Box<Expression>),
+    Comma(Box<Expression>, Box<Expression>),
+    Or(Box<Expression>, Box<Expression>),
+    And(Box<Expression>, Box<Expression>),
+    Compare(Box<Expression>, Comparison, Box<Expression>),
+    Array(Option<Box<Expression>>),
+    /// Members named with JSON escapes, as object keys are stored.
+    Object(Vec<(std::string::String, Expression)>),
+    Select(Box<Expression>),
+    Delete(Box<Expression>),
+    Keys,
+    Length,
+    Not,
+}
+
+#[derive(Clone, Copy, Debug)]
+enum Comparison {
+    Equal,
+    NotEqual,
+    Less,
+    LessOrEqual,
+    Greater,
+    GreaterOrEqual,
+}
+
+/// One step of the path to a value, as `del` finds them.
+#[derive(Clone, Debug, Eq, Ord, PartialEq, PartialOrd)]
+enum Step {
+    Index(usize),
+    /// An unescaped member name.
+    Member(std::string::String),
+}
+
+/// Stands for the members and elements that do not exist, which jq reads as
+/// null.
+const NULL: Value<'static> = Value::Null(Null {
+    span: Span { start: 0, end: 0 },
+    comments: None,
+});
+
+impl Filter {
+    pub fn parse(filter: &str) -> Result<Filter, FilterError> {
+        let pipe = FilterParser::parse(Rule::filter, filter)
+            .map_err(|error| FilterError::Syntax(Box::new(error)))?
+            .next()
+            .unwrap();
+
+        Ok(Filter {
+            expression: expression(pipe)?,
+        })
+    }
+
+    /// Runs the filter on `input`, returning its outputs in order.
+    pub fn apply<'a>(&self, input: &Value<'a>) -> Result<Vec<Value<'a>>, FilterError> {
+        evaluate(&self.expression, input)
+    }
+}
+
+impl fmt::Display for FilterError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FilterError::Syntax(error) => write!(f, "invalid filter\n{error}"),
+            FilterError::Apply(message) => write!(f, "{message}"),
+        }
+    }
+}
+
+impl std::error::Error for FilterError {}
+
+fn invalid(pair: &Pair<Rule>, message: std::string::String) -> FilterError {
+    FilterError::Syntax(Box::new(Error::new_from_span(
+        ErrorVariant::CustomError { message },
+        pair.as_span(),
+    )))
+}
+
+fn expression(pair: Pair<Rule>) -> Result<Expression, FilterError> {
+    let rule = pair.as_rule();
+
+    Ok(match rule {
+        Rule::pipe | Rule::comma | Rule::or_expr | Rule::and_expr => {
+            let mut operands = pair
+                .into_inner()
+                .filter(|pair| !matches!(pair.as_rule(), Rule::or | Rule::and))
+                .map(expression);
+            let first = operands.next().unwrap()?;
+
+            operands.try_fold(first, |left, right| {
+                let (left, right) = (Box::new(left), Box::new(right?));
+                Ok(match rule {
+                    Rule::pipe => Expression::Pipe(left, right),
+                    Rule::comma => Expression::Comma(left, right),
+                    Rule::or_expr => Expression::Or(left, right),
+                    _ => Expression::And(left, right),
+                })
+            })?
+        }
+        Rule::comparison => {
+            let mut inner = pair.into_inner();
+            let left = expression(inner.next().unwrap())?;
+            let Some(operator) = inner.next() else {
+                return Ok(left);
+            };
+            let comparison = match operator.as_str() {
+                "==" => Comparison::Equal,
+                "!=" => Comparison::NotEqual,
+                "<" => Comparison::Less,
+                "<=" => Comparison::LessOrEqual,
+                ">" => Comparison::Greater,
+                _ => Comparison::GreaterOrEqual,
+            };
+            let right = expression(inner.next().unwrap())?;
+
+            Expression::Compare(Box::new(left), comparison, Box::new(right))
+        }
+        Rule::postfix => {
+            let mut inner = pair.into_inner();
+            let term = expression(inner.next().unwrap())?;
+
+            inner.try_fold(term, |target, suffix| suffixed(target, suffix))?
+        }
+        Rule::identity => Expression::Identity,
+        Rule::field => suffixed(Expression::Identity, pair)?,
+        Rule::number => Expression::Literal(Value::from(Number {
+            lexeme: Cow::Owned(pair.as_str().to_string()),
+            ..Number::default()
+        })),
+        Rule::string => Expression::Literal(Value::from(String {
+            value: Cow::Owned(escaped(&string(pair)?)),
+            ..String::default()
+        })),
+        Rule::boolean => Expression::Literal(Value::from(Boolean {
+            value: pair.as_str() == "true",
+            ..Boolean::default()
+        })),
+        Rule::null => Expression::Literal(NULL),
+        Rule::array => Expression::Array(match pair.into_inner().next() {
+            Some(pipe) => Some(Box::new(expression(pipe)?)),
+            None => None,
+        }),
+        Rule::object => Expression::Object(
+            pair.into_inner()
+                .map(|entry| {
+                    let mut inner = entry.into_inner();
+                    let key = inner.next().unwrap();
+                    let name = match key.as_rule() {
+                        Rule::string => string(key)?,
+                        _ => key.as_str().to_string(),
+                    };
+                    let value = match inner.next() {
+                        Some(value) => expression(value)?,
+                        None => Expression::Field(Box::new(Expression::Identity), name.clone()),
+                    };
+
+                    Ok((escaped(&name), value))
+                })
+                .collect::<Result<_, FilterError>>()?,
+        ),
+        Rule::paren => expression(pair.into_inner().next().unwrap())?,
+        _ => function(pair)?,
+    })
+}
+
+/// Applies a `.name`, `[]` or `[index]` suffix to `target`.
+fn suffixed(target: Expression, suffix: Pair<Rule>) -> Result<Expression, FilterError> {
+    let target = Box::new(target);
+
+    Ok(match suffix.as_rule() {
+        Rule::field => {
+            let name = suffix.into_inner().next().unwrap();
+            let name = match name.as_rule() {
+                Rule::string => string(name)?,
+                _ => name.as_str().to_string(),
+            };
+
+            Expression::Field(target, name)
+        }
+        Rule::iterate => Expression::Iterate(target),
+        _ => Expression::Index(
+            target,
+            Box::new(expression(suffix.into_inner().next().unwrap())?),
+        ),
+    })
+}
+
+fn function(pair: Pair<Rule>) -> Result<Expression, FilterError> {
+    let call = pair.clone();
+    let mut inner = pair.into_inner();
+    let name = inner.next().unwrap().as_str();
+    let argument = match inner.next() {
+        Some(argument) => Some(Box::new(expression(argument)?)),
+        None => None,
+    };
+
+    Ok(match (name, argument) {
+        ("select", Some(condition)) => Expression::Select(condition),
+        // map(f) is defined as [.[] | f].
+        ("map", Some(f)) => Expression::Array(Some(Box::new(Expression::Pipe(
+            Box::new(Expression::Iterate(Box::new(Expression::Identity))),
+            f,
+        )))),
+        ("del", Some(paths)) => Expression::Delete(paths),
+        ("keys", None) => Expression::Keys,
+        ("length", None) => Expression::Length,
+        ("not", None) => Expression::Not,
+        (name, argument) => {
+            let arity = if argument.is_some() { 1 } else { 0 };
+            return Err(invalid(
+                &call,
+                format!("{name}/{arity} is not a known function"),
+            ));
+        }
+    })
+}
+
+/// The contents of a string literal without their escapes.
+fn string(pair: Pair<Rule>) -> Result<std::string::String, FilterError> {
+    let inner = pair.into_inner().next().unwrap();
+
+    unescape(inner.as_str()).map_err(|error| invalid(&inner, error.to_string()))
+}
+
+/// Writes `text` as the contents of a JSON string.
+fn escaped(text: &str) -> std::string::String {
+    let mut escaped = std::string::String::with_capacity(text.len());
+    for c in text.chars() {
+        escape_char(c, &mut escaped);
+    }
+
+    escaped
+}
+
+fn evaluate<'a>(expression: &Expression, input: &Value<'a>) -> Result<Vec<Value<'a>>, FilterError> {
+    Ok(match expression {
+        Expression::Identity => vec![input.clone()],
+        Expression::Field(target, name) => evaluate(target, input)?
+            .iter()
+            .map(|value| match value {
+                Value::Object(object) => Ok(object.get(name).unwrap_or(&NULL).clone()),
+                Value::Null(_) => Ok(NULL),
+                _ => Err(cannot(&format!("index {} with {name:?}", kind(value)))),
+            })
+            .collect::<Result<_, _>>()?,
+        Expression::Index(target, index) => {
+            let mut outputs = Vec::new();
+            for value in evaluate(target, input)? {
+                for index in evaluate(index, input)? {
+                    outputs.push(element(&value, &index)?.unwrap_or(&NULL).clone());
+                }
+            }
+
+            outputs
+        }
+        Expression::Iterate(target) => {
+            let mut outputs = Vec::new();
+            for value in evaluate(target, input)? {
+                match value {
+                    Value::Array(array) => outputs.extend(array.values),
+                    Value::Object(object) => {
+                        outputs.extend(object.members.into_iter().map(|(_, value)| value))
+                    }
+                    value => return Err(cannot(&format!("iterate over {}", kind(&value)))),
+                }
+            }
+
+            outputs
+        }
+        Expression::Literal(value) => vec![value.clone()],
+        Expression::Pipe(left, right) => {
+            let mut outputs = Vec::new();
+            for value in evaluate(left, input)? {
+                outputs.extend(evaluate(right, &value)?);
+            }
+
+            outputs
+        }
+        Expression::Comma(left, right) => {
+            let mut outputs = evaluate(left, input)?;
+            outputs.extend(evaluate(right, input)?);
+
+            outputs
+        }
+        Expression::Or(left, right) | Expression::And(left, right) => {
+            let or = matches!(expression, Expression::Or(..));
+            let mut outputs = Vec::new();
+            for value in evaluate(left, input)? {
+                // `or` stops at a true left side, and `and` at a false one.
+                if truthy(&value) == or {
+                    outputs.push(boolean(or));
+                    continue;
+                }
+                for value in evaluate(right, input)? {
+                    outputs.push(boolean(truthy(&value)));
+                }
+            }
+
+            outputs
+        }
+        Expression::Compare(left, comparison, right) => {
+            let mut outputs = Vec::new();
+            for left in evaluate(left, input)? {
+                for right in evaluate(right, input)? {
+                    let order = order(&left, &right);
+                    outputs.push(boolean(match comparison {
+                        Comparison::Equal => order == Ordering::Equal,
+                        Comparison::NotEqual => order != Ordering::Equal,
+                        Comparison::Less => order == Ordering::Less,
+                        Comparison::LessOrEqual => order != Ordering::Greater,
+                        Comparison::Greater => order == Ordering::Greater,
+                        Comparison::GreaterOrEqual => order != Ordering::Less,
+                    }));
+                }
+            }
+
+            outputs
+        }
+        Expression::Array(elements) => {
+            let values = match elements {
+                Some(elements) => evaluate(elements, input)?,
+                None => Vec::new(),
+            };
+
+            vec![Value::from(Array {
+                values,
+                ..Array::default()
+            })]
+        }
+        Expression::Object(entries) => {
+            // Each output of each value gives another object, as in jq.
+            let mut objects = vec![Vec::new()];
+            for (name, value) in entries {
+                let values = evaluate(value, input)?;
+                objects = objects
+                    .into_iter()
+                    .flat_map(|members: Vec<_>| {
+                        values.iter().map(move |value| {
+                            let mut members = members.clone();
+                            members.retain(|(key, _)| key != name);
+                            members.push((Cow::Owned(name.clone()), value.clone()));
+                            members
+                        })
+                    })
+                    .collect();
+            }
+
+            objects
+                .into_iter()
+                .map(|members| {
+                    Value::from(Object {
+                        members,
+                        ..Object::default()
+                    })
+                })
+                .collect()
+        }
+        Expression::Select(condition) => evaluate(condition, input)?
+            .iter()
+            .filter(|value| truthy(value))
+            .map(|_| input.clone())
+            .collect(),
+        Expression::Delete(paths) => {
+            let mut paths = self::paths(paths, input)?;
+            paths.sort();
+            paths.dedup();
+
+            // Later elements go first, so the indexes of earlier ones hold.
+            let mut output = input.clone();
+            for path in paths.iter().rev() {
+                delete(&mut output, path);
+            }
+
+            vec![output]
+        }
+        Expression::Keys => vec![keys(input)?],
+        Expression::Length => vec![length(input)?],
+        Expression::Not => vec![boolean(!truthy(input))],
+    })
+}
+
+fn cannot(action: &str) -> FilterError {
+    FilterError::Apply(format!("cannot {action}"))
+}
+
+fn kind(value: &Value) -> &'static str {
+    match value {
+        Value::Object(_) => "an object",
+        Value::Array(_) => "an array",
+        Value::String(_) => "a string",
+        Value::Number(_) => "a number",
+        Value::Bool(_) => "a boolean",
+        Value::Null(_) => "null",
+    }
+}
+
+/// Only false and null are false.
+fn truthy(value: &Value) -> bool {
+    !matches!(
+        value,
+        Value::Null(_) | Value::Bool(Boolean { value: false, .. })
+    )
+}
+
+fn boolean(value: bool) -> Value<'static> {
+    Value::from(Boolean {
+        value,
+        ..Boolean::default()
+    })
+}
+
+fn number(value: usize) -> Value<'static> {
+    Value::from(Number {
+        lexeme: Cow::Owned(value.to_string()),
+        ..Number::default()
+    })
+}
+
+fn float(number: &Number) -> f64 {
+    number.value().unwrap_or(f64::NAN)
+}
+
+/// The element of an array at an index, counting from the end when it is
+/// negative, or the member of an object with a name. `None` when there is
+/// none.
+fn element<'v, 'a>(
+    value: &'v Value<'a>,
+    index: &Value,
+) -> Result<Option<&'v Value<'a>>, FilterError> {
+    match (value, index) {
+        (Value::Array(array), Value::Number(number)) => {
+            let index = float(number).floor();
+            let length = array.values.len() as f64;
+            let index = if index < 0.0 { length + index } else { index };
+
+            Ok((0.0..length)
+                .contains(&index)
+                .then(|| &array.values[index as usize]))
+        }
+        (Value::Object(object), Value::String(name)) => Ok(object.get(&unescaped(&name.value))),
+        (Value::Null(_), Value::Number(_) | Value::String(_)) => Ok(None),
+        _ => Err(cannot(&format!(
+            "index {} with {}",
+            kind(value),
+            kind(index)
+        ))),
+    }
+}
+
+/// Keys of an object sorted by their code points, or the indexes of an
+/// array.
+fn keys(value: &Value) -> Result<Value<'static>, FilterError> {
+    let values = match value {
+        Value::Object(object) => {
+            let mut names: Vec<_> = object
+                .members
+                .iter()
+                .map(|(name, _)| unescaped(name).into_owned())
+                .collect();
+            names.sort();
+            names.dedup();
+
+            names
+                .iter()
+                .map(|name| {
+                    Value::from(String {
+                        value: Cow::Owned(escaped(name)),
+                        ..String::default()
+                    })
+                })
+                .collect()
+        }
+        Value::Array(array) => (0..array.values.len()).map(number).collect(),
+        _ => return Err(FilterError::Apply(format!("{} has no keys", kind(value)))),
+    };
+
+    Ok(Value::from(Array {
+        values,
+        ..Array::default()
+    }))
+}
+
+/// The number of elements, members or characters, the absolute value of a
+/// number, and 0 for null.
+fn length<'a>(value: &Value<'a>) -> Result<Value<'a>, FilterError> {
+    Ok(match value {
+        Value::Object(object) => number(object.members.len()),
+        Value::Array(array) => number(array.values.len()),
+        Value::String(string) => number(unescaped(&string.value).chars().count()),
+        Value::Number(number) => match number.lexeme.strip_prefix('-') {
+            Some(magnitude) => Value::from(Number {
+                lexeme: Cow::Owned(magnitude.to_string()),
+                ..Number::default()
+            }),
+            None => value.clone(),
+        },
+        Value::Null(_) => number(0),
+        Value::Bool(_) => return Err(FilterError::Apply("a boolean has no length".to_string())),
+    })
+}
+
+/// Orders values as jq does: null, false, true, numbers, strings, arrays and
+/// objects, each kind by its contents.
+fn order(a: &Value, b: &Value) -> Ordering {
+    let rank = |value: &Value| match value {
+        Value::Null(_) => 0,
+        Value::Bool(boolean) => 1 + boolean.value as u8,
+        Value::Number(_) => 3,
+        Value::String(_) => 4,
+        Value::Array(_) => 5,
+        Value::Object(_) => 6,
+    };
+
+    match (a, b) {
+        (Value::Number(a), Value::Number(b)) => a.compare(b).unwrap_or(Ordering::Equal),
+        (Value::String(a), Value::String(b)) => unescaped(&a.value).cmp(&unescaped(&b.value)),
+        (Value::Array(a), Value::Array(b)) => {
+            let elements = a.values.iter().zip(&b.values);
+            elements
+                .map(|(a, b)| order(a, b))
+                .find(|order| order.is_ne())
+                .unwrap_or(a.values.len().cmp(&b.values.len()))
+        }
+        // Objects compare their sorted keys first, then the values of each.
+        (Value::Object(_), Value::Object(_)) => order(&keys(a).unwrap(), &keys(b).unwrap())
+            .then_with(|| {
+                let Value::Array(names) = keys(a).unwrap() else {
+                    unreachable!()
+                };
+                names
+                    .values
+                    .iter()
+                    .map(|name| {
+                        order(
+                            element(a, name).unwrap().unwrap(),
+                            element(b, name).unwrap().unwrap(),
+                        )
+                    })
+                    .find(|order| order.is_ne())
+                    .unwrap_or(Ordering::Equal)
+            }),
+        _ => rank(a).cmp(&rank(b)),
+    }
+}
+
+/// The paths to the values an expression selects, for `del`. Only paths,
+/// iteration, pipes, commas and `select` can be followed.
+fn paths(expression: &Expression, input: &Value) -> Result<Vec<Vec<Step>>, FilterError> {
+    let mut found = Vec::new();
+
+    match expression {
+        Expression::Identity => found.push(Vec::new()),
+        Expression::Field(target, name) => {
+            for path in paths(target, input)? {
+                match at(input, &path) {
+                    Value::Object(_) | Value::Null(_) => {
+                        found.push([path, vec![Step::Member(name.clone())]].concat())
+                    }
+                    value => return Err(cannot(&format!("delete {name:?} from {}", kind(value)))),
+                }
+            }
+        }
+        Expression::Index(target, index) => {
+            for path in paths(target, input)? {
+                let value = at(input, &path);
+                for index in evaluate(index, input)? {
+                    let step = match (value, &index) {
+                        (Value::Array(array), Value::Number(number)) => {
+                            let index = float(number).floor();
+                            let length = array.values.len() as f64;
+                            let index = if index < 0.0 { length + index } else { index };
+                            if !(0.0..length).contains(&index) {
+                                continue;
+                            }
+                            Step::Index(index as usize)
+                        }
+                        (Value::Object(_) | Value::Null(_), Value::String(name)) => {
+                            Step::Member(unescaped(&name.value).into_owned())
+                        }
+                        _ => {
+                            return Err(cannot(&format!(
+                                "delete {} from {}",
+                                kind(&index),
+                                kind(value)
+                            )))
+                        }
+                    };
+                    found.push([path.clone(), vec![step]].concat());
+                }
+            }
+        }
+        Expression::Iterate(target) => {
+            for path in paths(target, input)? {
+                let steps: Vec<_> = match at(input, &path) {
+                    Value::Array(array) => (0..array.values.len()).map(Step::Index).collect(),
+                    Value::Object(object) => object
+                        .members
+                        .iter()
+                        .map(|(name, _)| Step::Member(unescaped(name).into_owned()))
+                        .collect(),
+                    value => return Err(cannot(&format!("iterate over {}", kind(value)))),
+                };
+                for step in steps {
+                    found.push([path.clone(), vec![step]].concat());
+                }
+            }
+        }
+        Expression::Pipe(left, right) => {
+            for path in paths(left, input)? {
+                for rest in paths(right, at(input, &path))? {
+                    found.push([path.clone(), rest].concat());
+                }
+            }
+        }
+        Expression::Comma(left, right) => {
+            found.extend(paths(left, input)?);
+            found.extend(paths(right, input)?);
+        }
+        Expression::Select(condition) => {
+            for value in evaluate(condition, input)? {
+                if truthy(&value) {
+                    found.push(Vec::new());
+                }
+            }
+        }
+        _ => {
+            return Err(FilterError::Apply(
+                "del can only delete paths, such as .a, .[0] or .[] | select(...)".to_string(),
+            ))
+        }
+    }
+
+    Ok(found)
+}
+
+/// The value at `path`, or null when there is none.
+fn at<'v, 'a>(value: &'v Value<'a>, path: &[Step]) -> &'v Value<'a> {
+    path.iter().fold(value, |value, step| match (value, step) {
+        (Value::Array(array), Step::Index(index)) => array.values.get(*index).unwrap_or(&NULL),
+        (Value::Object(object), Step::Member(name)) => object.get(name).unwrap_or(&NULL),
+        _ => &NULL,
+    })
+}
+
+/// Removes the value at `path`. Deleting the whole input leaves null.
+fn delete(value: &mut Value, path: &[Step]) {
+    let Some((step, rest)) = path.split_first() else {
+        *value = NULL;
+        return;
+    };
+
+    match (value, step, rest.is_empty()) {
+        (Value::Array(array), Step::Index(index), true) if *index < array.values.len() => {
+            array.values.remove(*index);
+        }
+        (Value::Object(object), Step::Member(name), true) => {
+            object
+                .members
+                .retain(|(key, _)| unescaped(key) != name.as_str());
+        }
+        (Value::Array(array), Step::Index(index), false) => {
+            if let Some(value) = array.values.get_mut(*index) {
+                delete(value, rest);
+            }
+        }
+        (Value::Object(object), Step::Member(name), false) => {
+            for (_, value) in object
+                .members
+                .iter_mut()
+                .filter(|(key, _)| unescaped(key) == name.as_str())
+            {
+                delete(value, rest);
+            }
+        }
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod filter_tests {
+    use super::*;
+    use crate::options::{Compact, Dialect, FormatOptions};
+
+    fn run(filter: &str, json: &str) -> Result<Vec<std::string::String>, FilterError> {
+        let input = crate::parser::parse(json, Dialect::Json5).unwrap();
+        let options = FormatOptions {
+            compact: Some(Compact::Tight),
+            ..FormatOptions::default()
+        };
+
+        Ok(Filter::parse(filter)?
+            .apply(&input)?
+            .iter()
+            .map(|value| crate::format_value(value.clone(), &options).unwrap())
+            .collect())
+    }
+
+    #[test]
+    fn it_follows_paths_and_pipes() {
+        let json = "{a: {b: [1, 2, 3]}, 'c d': null}";
+        let cases = [
+            (".", vec!["{\"a\":{\"b\":[1,2,3]},\"c d\":null}"]),
+            (".a.b[1]", vec!["2"]),
+            (".a.b[-1], .x.y", vec!["3", "null"]),
+            (".a.b[] | . , .", vec!["1", "1", "2", "2", "3", "3"]),
+            (".[\"c d\"], .\"c d\"", vec!["null", "null"]),
+            (".a | keys, length", vec!["[\"b\"]", "1"]),
+            (".a.b | map(. > 1)", vec!["[false,true,true]"]),
+            (
+                "[12345678901234567891] | .[0] < 12345678901234567892",
+                vec!["true"],
+            ),
+            ("{x: .a.b[0], a} | keys", vec!["[\"a\",\"x\"]"]),
+            ("{n: .a.b[]} | .n", vec!["1", "2", "3"]),
+        ];
+
+        for (filter, expected) in cases {
+            assert_eq!(expected, run(filter, json).unwrap(), "{filter}");
+        }
+    }
+
+    #[test]
+    fn it_selects_with_conditions() {
+        let json = "[{id: 1, tags: ['a']}, {id: 2, ok: false}, {id: 3, ok: true}]";
+        let cases = [
+            (".[] | select(.ok) | .id", vec!["3"]),
+            (".[] | select(.ok | not) | .id", vec!["1", "2"]),
+            (".[] | select(.id >= 2 and .ok != false) | .id", vec!["3"]),
+            (
+                "map(.tags == [\"a\"] or .id < 2)",
+                vec!["[true,false,false]"],
+            ),
+            (
+                "[{}, [], \"\", 0, true, false, null] | map(. < \"a\")",
+                vec!["[false,false,true,true,true,true,true]"],
+            ),
+        ];
+
+        for (filter, expected) in cases {
+            assert_eq!(expected, run(filter, json).unwrap(), "{filter}");
+        }
+    }
+
+    #[test]
+    fn it_deletes_paths() {
+        let json = "{a: [1, 2, 3, 4], b: {c: 1, d: 2}}";
+        let cases = [
+            ("del(.b)", "{\"a\":[1,2,3,4]}"),
+            ("del(.a[0, 2], .b.c)", "{\"a\":[2,4],\"b\":{\"d\":2}}"),
+            (
+                "del(.a[] | select(. > 2))",
+                "{\"a\":[1,2],\"b\":{\"c\":1,\"d\":2}}",
+            ),
+            ("del(.x.y)", "{\"a\":[1,2,3,4],\"b\":{\"c\":1,\"d\":2}}"),
+            ("del(.)", "null"),
+        ];
+
+        for (filter, expected) in cases {
+            assert_eq!(vec![expected], run(filter, json).unwrap(), "{filter}");
+        }
+    }
+
+    #[test]
+    fn it_reports_invalid_filters() {
+        let syntax = [
+            "", ".a |", "sort", "select", "keys(.)", "{a: }", ".[", "..a",
+        ];
+        for filter in syntax {
+            assert!(
+                matches!(Filter::parse(filter), Err(FilterError::Syntax(_))),
+                "{filter}"
+            );
+        }
+
+        let cases = [
+            (".a.b", "{a: 1}", "cannot index a number with \"b\""),
+            (".[]", "true", "cannot iterate over a boolean"),
+            ("length", "false", "a boolean has no length"),
+            ("del(.a | length)", "{a: 1}", "del can only delete paths"),
+        ];
+        for (filter, json, message) in cases {
+            let error = run(filter, json).unwrap_err().to_string();
+            assert!(error.starts_with(message), "{filter}: {error}");
+        }
+    }
+}
@@ -16,24 +16,96 @@ You should have received a copy of the GNU General Public License
 along with this program.  If not, see <http://www.gnu.org/licenses/>.
 */
 
-use crate::parser::parse;
+use crate::nodes::{FormatError, Node, Value};
+use crate::options::FormatOptions;
+use crate::parser::{parse, ParseError};
+use std::fmt;
+
+#[derive(Debug)]
+pub enum Error {
+    /// The input does not follow the syntax it was parsed with.
+    Parse(ParseError),
+    /// The input is valid but cannot be printed with the requested options.
+    Format(FormatError),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Parse(error) => write!(f, "{error}"),
+            Error::Format(error) => write!(f, "{error}"),
+        }
+    }
+}
+
+impl Error {
+    /// Names the input in the location of parse errors.
+    pub fn with_path(self, path: &str) -> Self {
+        match self {
+            Error::Parse(error) => Error::Parse(error.with_path(path)),
+            other => other,
+        }
+    }
+
+    /// Numbers the lines of parse errors from `first_line`.
+    pub fn on_line(self, first_line: usize) -> Self {
+        match self {
+            Error::Parse(error) => Error::Parse(error.on_line(first_line)),
+            other => other,
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<ParseError> for Error {
+    fn from(error: ParseError) -> Self {
+        Error::Parse(error)
+    }
+}
+
+impl From<FormatError> for Error {
+    fn from(error: FormatError) -> Self {
+        Error::Format(error)
+    }
+}
+
+/// Parses `json` in the input dialect of `options` and renders it as they
+/// describe.
+pub fn format_with(json: &str, options: &FormatOptions) -> Result<String, Error> {
+    let value = parse(json, options.input)?;
+
+    Ok(format_value(value, options)?)
+}
+
+/// Formats a value that has already been parsed.
+pub fn format_value(mut value: Value<'_>, options: &FormatOptions) -> Result<String, FormatError> {
+    if options.canonical {
+        return value.format_canonical();
+    }
+
+    if let Some(sort) = &options.sort {
+        value.sort_keys(sort, 0);
+    }
 
-pub fn format(json: &str) -> String {
-    match parse(json) {
-        Ok(value) => value.format_as_root(),
-        Err(error) => format!("[Error] {}", error),
+    match options.compact {
+        Some(compact) => value.format_compact(compact, options),
+        None => value.format_as_root(options),
     }
 }
 
 #[cfg(test)]
 mod json_formatter_tests {
     use super::*;
+    use crate::options::{Compact, Dialect, Indent, NumberFormat};
+    use crate::sort::{KeyOrdering, KeySort};
+    use crate::theme::Theme;
 
     #[test]
     fn it_formats_empty_object() {
         let input = "{}";
         let expected = "{}";
-        let result = format(input);
+        let result = format_with(input, &FormatOptions::default()).unwrap();
 
         assert_eq!(expected, result);
     }
@@ -42,7 +114,7 @@ mod json_formatter_tests {
     fn it_formats_empty_array() {
         let input = "[]";
         let expected = "[]";
-        let result = format(input);
+        let result = format_with(input, &FormatOptions::default()).unwrap();
 
         assert_eq!(expected, result);
     }
@@ -50,8 +122,8 @@ mod json_formatter_tests {
     #[test]
     fn it_formats_an_array_with_one_boolean_element() {
         let input = "[true]";
-        let expected = "[\n  true\n]";
-        let result = format(input);
+        let expected = "[true]";
+        let result = format_with(input, &FormatOptions::default()).unwrap();
 
         assert_eq!(expected, result);
     }
@@ -59,8 +131,8 @@ mod json_formatter_tests {
     #[test]
     fn it_formats_an_array_with_many_boolean_element() {
         let input = "[true,false]";
-        let expected = "[\n  true,\n  false\n]";
-        let result = format(input);
+        let expected = "[true, false]";
+        let result = format_with(input, &FormatOptions::default()).unwrap();
 
         assert_eq!(expected, result);
     }
@@ -68,8 +140,8 @@ mod json_formatter_tests {
     #[test]
     fn it_formats_an_array_of_null_values() {
         let input = "[false, null, null]";
-        let expected = "[\n  false,\n  null,\n  null\n]";
-        let result = format(input);
+        let expected = "[false, null, null]";
+        let result = format_with(input, &FormatOptions::default()).unwrap();
 
         assert_eq!(expected, result);
     }
@@ -77,8 +149,8 @@ mod json_formatter_tests {
     #[test]
     fn it_formats_an_array_of_numbers() {
         let input = "[1, 2, -1, 5e3]";
-        let expected = "[\n  1,\n  2,\n  -1,\n  5000\n]";
-        let result = format(input);
+        let expected = "[1, 2, -1, 5e3]";
+        let result = format_with(input, &FormatOptions::default()).unwrap();
 
         assert_eq!(expected, result);
     }
@@ -86,19 +158,22 @@ mod json_formatter_tests {
     #[test]
     fn it_formats_an_array_of_strings() {
         let input = "[\"hello\",\"world\"]";
-        let expected = "[\n  \"hello\",\n  \"world\"\n]";
-        let result = format(input);
+        let expected = "[\"hello\", \"world\"]";
+        let result = format_with(input, &FormatOptions::default()).unwrap();
 
         assert_eq!(expected, result);
     }
 
     #[test]
     fn it_formats_an_object() {
-        colored::control::set_override(true);
         let input = "{\"name\": \"Nico\",\"foo\": \"bar\"}";
-        let expected = "{\n  \u{1b}[1;95m\"name\"\u{1b}[0m: \"Nico\",\n  \u{1b}[1;95m\"foo\"\u{1b}[0m: \"bar\"\n}";
-        let result = format(input);
-        colored::control::unset_override();
+        let expected =
+            "{\u{1b}[1;95m\"name\"\u{1b}[0m: \u{1b}[32m\"Nico\"\u{1b}[0m, \u{1b}[1;95m\"foo\"\u{1b}[0m: \u{1b}[32m\"bar\"\u{1b}[0m}";
+        let options = FormatOptions {
+            theme: Theme::built_in("default"),
+            ..FormatOptions::default()
+        };
+        let result = format_with(input, &options).unwrap();
 
         assert_eq!(expected, result);
     }
@@ -106,8 +181,8 @@ mod json_formatter_tests {
     #[test]
     fn it_formats_multidensional_arrays() {
         let input = "[1, [3]]";
-        let expected = "[\n  1,\n  [\n    3\n  ]\n]";
-        let result = format(input);
+        let expected = "[1, [3]]";
+        let result = format_with(input, &FormatOptions::default()).unwrap();
 
         assert_eq!(expected, result);
     }
@@ -115,8 +190,10 @@ mod json_formatter_tests {
     #[test]
     fn it_does_not_panics_when_json_is_bad_formatted() {
         let input = "[1, 2, 3";
-        let expected = "[Error]  --> 1:8\n  |\n1 | [1, 2, 3\n  |        ^---\n  |\n  = expected object or array";
-        let result = format(input);
+        let expected = "invalid JSON\n --> 1:8\n  |\n1 | [1, 2, 3\n  |        ^---\n  |\n  = expected object or array";
+        let result = format_with(input, &FormatOptions::default())
+            .unwrap_err()
+            .to_string();
 
         assert_eq!(expected, result);
     }
@@ -124,32 +201,32 @@ mod json_formatter_tests {
     #[test]
     fn it_rejects_unescaped_newline_in_string() {
         let input = "\"hello\nworld\"";
-        let result = format(input);
+        let result = format_with(input, &FormatOptions::default());
 
-        assert!(result.starts_with("[Error]"));
+        assert!(matches!(result, Err(Error::Parse(_))));
     }
 
     #[test]
     fn it_rejects_unescaped_tab_in_string() {
         let input = "\"hello\tworld\"";
-        let result = format(input);
+        let result = format_with(input, &FormatOptions::default());
 
-        assert!(result.starts_with("[Error]"));
+        assert!(matches!(result, Err(Error::Parse(_))));
     }
 
     #[test]
     fn it_rejects_unescaped_carriage_return_in_string() {
         let input = "\"hello\rworld\"";
-        let result = format(input);
+        let result = format_with(input, &FormatOptions::default());
 
-        assert!(result.starts_with("[Error]"));
+        assert!(matches!(result, Err(Error::Parse(_))));
     }
 
     #[test]
     fn it_accepts_escaped_control_characters_in_string() {
         let input = "\"hello\\nworld\"";
         let expected = "\"hello\\nworld\"";
-        let result = format(input);
+        let result = format_with(input, &FormatOptions::default()).unwrap();
 
         assert_eq!(expected, result);
     }
@@ -158,8 +235,213 @@ mod json_formatter_tests {
     fn it_accepts_unicode_escape_in_string() {
         let input = "\"caf\\u00e9\"";
         let expected = "\"caf\\u00e9\"";
-        let result = format(input);
+        let result = format_with(input, &FormatOptions::default()).unwrap();
 
         assert_eq!(expected, result);
     }
+
+    #[test]
+    fn it_formats_with_custom_indentation() {
+        let input = "[1, [3]]";
+        let expected = "[\n    1,\n    [\n        3\n    ]\n]";
+        let options = FormatOptions {
+            indent: Indent::Spaces(4),
+            max_width: 0,
+            ..FormatOptions::default()
+        };
+        let result = format_with(input, &options).unwrap();
+
+        assert_eq!(expected, result);
+    }
+
+    #[test]
+    fn it_formats_compact_output() {
+        let input = "[1, {}, [], [true, null, \"a b\"]]";
+        let options = FormatOptions {
+            compact: Some(Compact::Tight),
+            ..FormatOptions::default()
+        };
+        let expected = "[1,{},[],[true,null,\"a b\"]]";
+        let result = format_with(input, &options).unwrap();
+
+        assert_eq!(expected, result);
+    }
+
+    #[test]
+    fn it_formats_spaced_compact_output() {
+        let input = "[\n  1,\n  [true, null]\n]";
+        let options = FormatOptions {
+            compact: Some(Compact::Spaced),
+            ..FormatOptions::default()
+        };
+        let expected = "[1, [true, null]]";
+        let result = format_with(input, &options).unwrap();
+
+        assert_eq!(expected, result);
+    }
+
+    #[test]
+    fn it_breaks_containers_wider_than_max_width() {
+        let input = "[[1.5, 2.5], [\"first\", \"second\", \"third\"]]";
+        let expected =
+            "[\n  [1.5, 2.5],\n  [\n    \"first\",\n    \"second\",\n    \"third\"\n  ]\n]";
+        let options = FormatOptions {
+            max_width: 20,
+            ..FormatOptions::default()
+        };
+        let result = format_with(input, &options).unwrap();
+
+        assert_eq!(expected, result);
+    }
+
+    #[test]
+    fn it_does_not_change_numbers() {
+        let input = "[1.0, 12345678901234567891, 1e400, 0.1e-2]";
+        let expected = "[1.0, 12345678901234567891, 1e400, 0.1e-2]";
+        let result = format_with(input, &FormatOptions::default()).unwrap();
+
+        assert_eq!(expected, result);
+    }
+
+    #[test]
+    fn it_normalizes_numbers() {
+        let input = "[1.0, 5e3, 1E30]";
+        let expected = "[1,5000,1e+30]";
+        let options = FormatOptions {
+            compact: Some(Compact::Tight),
+            numbers: NumberFormat::Canonical,
+            ..FormatOptions::default()
+        };
+        let result = format_with(input, &options).unwrap();
+
+        assert_eq!(expected, result);
+    }
+
+    #[test]
+    fn it_reports_numbers_that_cannot_be_normalized() {
+        let input = "[1, 1e400]";
+        let expected = FormatError::NumberOutOfRange("1e400".to_string());
+        let options = FormatOptions {
+            numbers: NumberFormat::Decimal,
+            ..FormatOptions::default()
+        };
+        let result = format_with(input, &options);
+
+        assert!(matches!(result, Err(Error::Format(error)) if error == expected));
+    }
+
+    #[test]
+    fn it_sorts_keys() {
+        let input = "{\"version\": 1, \"b\": {\"y\": 1, \"x\": 2}, \"name\": \"a\"}";
+        let expected = "{\"name\":\"a\",\"version\":1,\"b\":{\"x\":2,\"y\":1}}";
+        let options = FormatOptions {
+            compact: Some(Compact::Tight),
+            sort: Some(KeySort {
+                ordering: Some(KeyOrdering::Lexical),
+                priority: vec!["name".to_string(), "version".to_string()],
+                depth: None,
+            }),
+            ..FormatOptions::default()
+        };
+        let result = format_with(input, &options).unwrap();
+
+        assert_eq!(expected, result);
+    }
+
+    #[test]
+    fn it_formats_canonical_json() {
+        // Example from RFC 8785, section 3.2.2.
+        let input = r#"{
+  "numbers": [333333333.33333329, 1E30, 4.50, 2e-3, 0.000000000000000000000000001],
+  "string": "\u20ac$\u000F\u000aA'\u0042\u0022\u005c\\\"\/",
+  "literals": [null, true, false]
+}"#;
+        let expected = r#"{"literals":[null,true,false],"numbers":[333333333.3333333,1e+30,4.5,0.002,1e-27],"string":"€$\u000f\nA'B\"\\\\\"/"}"#;
+        let options = FormatOptions {
+            canonical: true,
+            ..FormatOptions::default()
+        };
+        let result = format_with(input, &options).unwrap();
+
+        assert_eq!(expected, result);
+    }
+
+    #[test]
+    fn it_sorts_canonical_keys_by_utf16_code_units() {
+        // Example from RFC 8785, section 3.2.3.
+        let input = r#"{
+  "\u20ac": "Euro Sign",
+  "\r": "Carriage Return",
+  "\ufb33": "Hebrew Letter Dalet With Dagesh",
+  "1": "One",
+  "\ud83d\ude00": "Emoji: Grinning Face",
+  "\u0080": "Control",
+  "\u00f6": "Latin Small Letter O With Diaeresis"
+}"#;
+        let expected = "{\"\\r\":\"Carriage Return\",\"1\":\"One\",\"\u{80}\":\"Control\",\"ö\":\"Latin Small Letter O With Diaeresis\",\"€\":\"Euro Sign\",\"😀\":\"Emoji: Grinning Face\",\"\u{fb33}\":\"Hebrew Letter Dalet With Dagesh\"}";
+        let options = FormatOptions {
+            canonical: true,
+            ..FormatOptions::default()
+        };
+        let result = format_with(input, &options).unwrap();
+
+        assert_eq!(expected, result);
+    }
+
+    #[test]
+    fn it_refuses_duplicate_names_in_canonical_json() {
+        let options = FormatOptions {
+            canonical: true,
+            ..FormatOptions::default()
+        };
+        let result = format_with(r#"{"a": {"b": 1, "\u0062": 2}}"#, &options);
+
+        assert_eq!(
+            "name \"b\" is used by more than one member and cannot be written as canonical JSON",
+            result.unwrap_err().to_string()
+        );
+    }
+
+    fn json5(output: Dialect) -> FormatOptions {
+        FormatOptions {
+            input: Dialect::Json5,
+            output,
+            ..FormatOptions::default()
+        }
+    }
+
+    #[test]
+    fn it_normalizes_json5_to_json() {
+        let input = r#"// Server settings
+{
+  host: 'localhost', /* or an address */
+  port: 0x1F90,
+  ratio: .5,
+  'quoted key': "it's",
+  limits: [+1, 2.,],
+}"#;
+        let expected = "{\n  \"host\": \"localhost\",\n  \"port\": 8080,\n  \"ratio\": 0.5,\n  \"quoted key\": \"it's\",\n  \"limits\": [1, 2]\n}";
+        let result = format_with(input, &json5(Dialect::Json)).unwrap();
+
+        assert_eq!(expected, result);
+    }
+
+    #[test]
+    fn it_reemits_json5() {
+        let input = "{'a-b': 'x', c: Infinity, d: 0xFF, e: [1,],}";
+        let expected = "{\"a-b\": \"x\", c: Infinity, d: 0xFF, e: [1]}";
+        let result = format_with(input, &json5(Dialect::Json5)).unwrap();
+
+        assert_eq!(expected, result);
+    }
+
+    #[test]
+    fn it_rejects_json5_values_without_a_json_form() {
+        let error = format_with("[NaN]", &json5(Dialect::Json)).unwrap_err();
+
+        assert!(matches!(
+            error,
+            Error::Format(FormatError::NonFiniteNumber(_))
+        ));
+    }
 }
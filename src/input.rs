@@ -0,0 +1,361 @@
+/*
+jfmt
+Copyright (C) 2024 - Víctor Berga
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <http://www.gnu.org/licenses/>.
+*/
+
+use std::fmt;
+use std::fs;
+use std::io::{self, BufRead, BufReader, Read};
+use std::path::{Path, PathBuf};
+
+/// Where a document is read from.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Source {
+    Stdin,
+    File(PathBuf),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Encoding {
+    Utf8,
+    Utf16Le,
+    Utf16Be,
+    Utf32Le,
+    Utf32Be,
+}
+
+/// An input that could not be turned into text.
+#[derive(Debug)]
+pub struct InputError {
+    pub path: String,
+    pub kind: InputErrorKind,
+}
+
+#[derive(Debug)]
+pub enum InputErrorKind {
+    Io(io::Error),
+    /// The bytes are not valid in the encoding, starting at `offset`.
+    Encoding {
+        encoding: Encoding,
+        offset: usize,
+    },
+}
+
+impl fmt::Display for Encoding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Encoding::Utf8 => "UTF-8",
+            Encoding::Utf16Le => "UTF-16LE",
+            Encoding::Utf16Be => "UTF-16BE",
+            Encoding::Utf32Le => "UTF-32LE",
+            Encoding::Utf32Be => "UTF-32BE",
+        };
+
+        write!(f, "{name}")
+    }
+}
+
+impl fmt::Display for InputError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.kind {
+            InputErrorKind::Io(error) => write!(f, "{}: {error}", self.path),
+            InputErrorKind::Encoding { encoding, offset } => {
+                write!(f, "{}: invalid {encoding} at byte {offset}", self.path)
+            }
+        }
+    }
+}
+
+impl std::error::Error for InputError {}
+
+impl Source {
+    /// Name used for the source in diagnostics.
+    pub fn name(&self) -> String {
+        match self {
+            Source::Stdin => "<stdin>".to_string(),
+            Source::File(path) => path.display().to_string(),
+        }
+    }
+}
+
+/// Expands the paths given on the command line into the documents to format.
+/// `-` stands for standard input, and directories are searched recursively
+/// for `*.json`, `*.json5` and `*.jsonc` files, skipping hidden entries and
+/// not following links to directories.
+pub fn collect(paths: &[PathBuf]) -> Result<Vec<Source>, InputError> {
+    let mut sources = Vec::new();
+
+    for path in paths {
+        if path.as_os_str() == "-" {
+            sources.push(Source::Stdin);
+        } else if path.is_dir() {
+            walk(path, &mut sources)?;
+        } else {
+            sources.push(Source::File(path.clone()));
+        }
+    }
+
+    Ok(sources)
+}
+
+fn walk(directory: &Path, sources: &mut Vec<Source>) -> Result<(), InputError> {
+    let error = |io| InputError {
+        path: directory.display().to_string(),
+        kind: InputErrorKind::Io(io),
+    };
+
+    let mut entries = fs::read_dir(directory)
+        .and_then(|entries| {
+            entries
+                .map(|entry| {
+                    let entry = entry?;
+                    Ok((entry.path(), entry.file_type()?))
+                })
+                .collect::<io::Result<Vec<_>>>()
+        })
+        .map_err(error)?;
+    entries.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+    for (path, file_type) in entries {
+        let hidden = path
+            .file_name()
+            .is_some_and(|name| name.to_string_lossy().starts_with('.'));
+        if hidden {
+            continue;
+        }
+
+        if file_type.is_dir() {
+            walk(&path, sources)?;
+        } else if file_type.is_symlink() && path.is_dir() {
+            // Linked directories are not followed, as they may lead back up
+            // the tree.
+            continue;
+        } else if path
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .is_some_and(|extension| matches!(extension, "json" | "json5" | "jsonc"))
+        {
+            sources.push(Source::File(path));
+        }
+    }
+
+    Ok(())
+}
+
+/// Reads a whole input and decodes it into text.
+pub fn read(source: &Source) -> Result<String, InputError> {
+    let error = |kind| InputError {
+        path: source.name(),
+        kind,
+    };
+
+    let bytes = match source {
+        Source::Stdin => {
+            let mut bytes = Vec::new();
+            io::stdin().lock().read_to_end(&mut bytes).map(|_| bytes)
+        }
+        Source::File(path) => fs::read(path),
+    }
+    .map_err(|io| error(InputErrorKind::Io(io)))?;
+
+    decode(&bytes)
+        .map_err(|(encoding, offset)| error(InputErrorKind::Encoding { encoding, offset }))
+}
+
+/// Opens an input to be read as it goes, past a UTF-8 byte order mark.
+/// Inputs that start with a UTF-16 or UTF-32 byte order mark give `None`.
+pub fn open(source: &Source) -> Result<Option<Box<dyn BufRead>>, InputError> {
+    let error = |io| InputError {
+        path: source.name(),
+        kind: InputErrorKind::Io(io),
+    };
+
+    let mut reader: Box<dyn BufRead> = match source {
+        Source::Stdin => Box::new(io::stdin().lock()),
+        Source::File(path) => Box::new(BufReader::new(fs::File::open(path).map_err(error)?)),
+    };
+
+    match byte_order_mark(reader.fill_buf().map_err(error)?) {
+        (Encoding::Utf8, bom) => {
+            reader.consume(bom);
+            Ok(Some(reader))
+        }
+        _ => Ok(None),
+    }
+}
+
+/// Encoding given by the byte order mark at the start of `bytes`, UTF-8 when
+/// there is none, and the length of the mark.
+fn byte_order_mark(bytes: &[u8]) -> (Encoding, usize) {
+    match bytes {
+        [0xFF, 0xFE, 0x00, 0x00, ..] => (Encoding::Utf32Le, 4),
+        [0x00, 0x00, 0xFE, 0xFF, ..] => (Encoding::Utf32Be, 4),
+        [0xEF, 0xBB, 0xBF, ..] => (Encoding::Utf8, 3),
+        [0xFF, 0xFE, ..] => (Encoding::Utf16Le, 2),
+        [0xFE, 0xFF, ..] => (Encoding::Utf16Be, 2),
+        _ => (Encoding::Utf8, 0),
+    }
+}
+
+/// Decodes UTF-8 text, or UTF-16 and UTF-32 text that starts with a byte
+/// order mark. The byte order mark is not part of the result.
+pub fn decode(bytes: &[u8]) -> Result<String, (Encoding, usize)> {
+    let (encoding, bom) = byte_order_mark(bytes);
+    let body = &bytes[bom..];
+    let invalid = |offset: usize| (encoding, bom + offset);
+
+    match encoding {
+        Encoding::Utf8 => std::str::from_utf8(body)
+            .map(str::to_string)
+            .map_err(|error| invalid(error.valid_up_to())),
+        Encoding::Utf16Le | Encoding::Utf16Be => {
+            if !body.len().is_multiple_of(2) {
+                return Err(invalid(body.len() - 1));
+            }
+            let units = body.chunks(2).map(|pair| match encoding {
+                Encoding::Utf16Le => u16::from_le_bytes([pair[0], pair[1]]),
+                _ => u16::from_be_bytes([pair[0], pair[1]]),
+            });
+
+            let mut text = String::with_capacity(body.len() / 2);
+            let mut offset = 0;
+            for c in char::decode_utf16(units) {
+                let c = c.map_err(|_| invalid(offset))?;
+                offset += c.len_utf16() * 2;
+                text.push(c);
+            }
+
+            Ok(text)
+        }
+        Encoding::Utf32Le | Encoding::Utf32Be => {
+            if !body.len().is_multiple_of(4) {
+                return Err(invalid(body.len() - body.len() % 4));
+            }
+
+            body.chunks(4)
+                .enumerate()
+                .map(|(index, quad)| {
+                    let quad = [quad[0], quad[1], quad[2], quad[3]];
+                    let scalar = match encoding {
+                        Encoding::Utf32Le => u32::from_le_bytes(quad),
+                        _ => u32::from_be_bytes(quad),
+                    };
+
+                    char::from_u32(scalar).ok_or(invalid(index * 4))
+                })
+                .collect()
+        }
+    }
+}
+
+#[cfg(test)]
+mod input_tests {
+    use super::*;
+    use crate::scratch::Scratch;
+
+    fn utf16(text: &str, little_endian: bool) -> Vec<u8> {
+        let mut bytes = if little_endian {
+            vec![0xFF, 0xFE]
+        } else {
+            vec![0xFE, 0xFF]
+        };
+        for unit in text.encode_utf16() {
+            let pair = if little_endian {
+                unit.to_le_bytes()
+            } else {
+                unit.to_be_bytes()
+            };
+            bytes.extend(pair);
+        }
+
+        bytes
+    }
+
+    #[test]
+    fn it_decodes_plain_utf8() {
+        assert_eq!(Ok("[\"é\"]".to_string()), decode("[\"é\"]".as_bytes()));
+    }
+
+    #[test]
+    fn it_strips_the_utf8_byte_order_mark() {
+        assert_eq!(Ok("{}".to_string()), decode(b"\xEF\xBB\xBF{}"));
+    }
+
+    #[test]
+    fn it_transcodes_utf16() {
+        let text = "{\"a\": \"😀\"}";
+
+        assert_eq!(Ok(text.to_string()), decode(&utf16(text, true)));
+        assert_eq!(Ok(text.to_string()), decode(&utf16(text, false)));
+    }
+
+    #[test]
+    fn it_transcodes_utf32() {
+        let mut bytes = vec![0x00, 0x00, 0xFE, 0xFF];
+        for c in "[1]".chars() {
+            bytes.extend((c as u32).to_be_bytes());
+        }
+
+        assert_eq!(Ok("[1]".to_string()), decode(&bytes));
+    }
+
+    #[test]
+    fn it_reports_where_the_encoding_is_invalid() {
+        assert_eq!(Err((Encoding::Utf8, 2)), decode(b"[\"\xFF\"]"));
+        assert_eq!(
+            Err((Encoding::Utf16Le, 2)),
+            decode(&[0xFF, 0xFE, 0x00, 0xD8])
+        );
+    }
+
+    #[test]
+    fn it_reports_the_path_of_missing_files() {
+        let source = Source::File(PathBuf::from("no/such/file.json"));
+        let error = read(&source).unwrap_err();
+
+        assert_eq!("no/such/file.json", error.path);
+        assert!(matches!(error.kind, InputErrorKind::Io(_)));
+    }
+
+    #[test]
+    fn it_collects_json_files_from_directories() {
+        let scratch = Scratch::new("collect");
+        for file in [
+            "b.json",
+            "a.json",
+            "notes.txt",
+            "nested/c.json5",
+            ".hidden/d.json",
+        ] {
+            scratch.write(file, "{}");
+        }
+        let root = scratch.directory();
+
+        #[cfg(unix)]
+        std::os::unix::fs::symlink("..", root.join("nested").join("up")).unwrap();
+
+        let paths = [PathBuf::from("-"), root.to_path_buf()];
+        let expected = vec![
+            Source::Stdin,
+            Source::File(root.join("a.json")),
+            Source::File(root.join("b.json")),
+            Source::File(root.join("nested").join("c.json5")),
+        ];
+        let result = collect(&paths);
+
+        assert_eq!(expected, result.unwrap());
+    }
+}
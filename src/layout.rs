@@ -0,0 +1,237 @@
+/*
+jfmt
+Copyright (C) 2024 - Víctor Berga
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <http://www.gnu.org/licenses/>.
+*/
+
+//! A small Wadler-style pretty printing engine.
+//!
+//! Nodes describe their output as a [`Doc`] and [`render`] decides, group by
+//! group, whether the contents fit on the current line or have to be broken.
+
+use crate::options::FormatOptions;
+use crate::theme::Token;
+use std::fmt;
+
+pub enum Doc {
+    /// Literal text together with its width in columns. The width is kept
+    /// apart because the text may contain ANSI color sequences.
+    Text(String, usize),
+    /// A line break when the enclosing group is broken, or the given text
+    /// when it is printed flat.
+    Line(&'static str),
+    /// Indents every line break inside by one level.
+    Nest(Box<Doc>),
+    /// Contents that are printed flat if they fit in the remaining width.
+    Group(Box<Doc>),
+    Concat(Vec<Doc>),
+    /// Prints nothing, but keeps every enclosing group from being flat.
+    BreakParent,
+}
+
+impl Doc {
+    pub fn text(text: impl Into<String>) -> Doc {
+        let text = text.into();
+        let width = text.chars().count();
+
+        Doc::Text(text, width)
+    }
+
+    /// Text painted with the theme style for `token`.
+    pub fn token(text: impl Into<String>, token: Token, options: &FormatOptions) -> Doc {
+        let text = text.into();
+        let width = text.chars().count();
+
+        Doc::Text(options.paint(token, &text), width)
+    }
+
+    pub fn nest(doc: Doc) -> Doc {
+        Doc::Nest(Box::new(doc))
+    }
+
+    pub fn group(doc: Doc) -> Doc {
+        Doc::Group(Box::new(doc))
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Mode {
+    Flat,
+    Break,
+}
+
+/// Writes `doc` into `output`, breaking the groups that do not fit.
+pub fn render(doc: &Doc, options: &FormatOptions, output: &mut dyn fmt::Write) -> fmt::Result {
+    let mut column = 0;
+    let mut stack = vec![(0, Mode::Break, doc)];
+    // Consecutive lines are mostly at the same level, so the indentation of
+    // the last one is kept.
+    let mut indentation = (0, String::new());
+
+    while let Some((level, mode, doc)) = stack.pop() {
+        match doc {
+            Doc::Text(text, width) => {
+                output.write_str(text)?;
+                column += width;
+            }
+            Doc::Line(flat) => match mode {
+                Mode::Flat => {
+                    output.write_str(flat)?;
+                    column += flat.len();
+                }
+                Mode::Break => {
+                    if indentation.0 != level {
+                        indentation = (level, options.indentation(level));
+                    }
+                    output.write_str(options.newline())?;
+                    output.write_str(&indentation.1)?;
+                    column = indentation.1.chars().count();
+                }
+            },
+            Doc::Nest(doc) => stack.push((level + 1, mode, doc)),
+            Doc::Group(doc) => {
+                let remaining = options.max_width as isize - column as isize;
+                let mode = match mode {
+                    Mode::Flat => Mode::Flat,
+                    Mode::Break if fits(remaining, doc, &stack) => Mode::Flat,
+                    Mode::Break => Mode::Break,
+                };
+                stack.push((level, mode, doc));
+            }
+            Doc::Concat(docs) => {
+                stack.extend(docs.iter().rev().map(|doc| (level, mode, doc)));
+            }
+            Doc::BreakParent => {}
+        }
+    }
+
+    Ok(())
+}
+
+/// Checks whether `doc` printed flat, followed by whatever comes after it up
+/// to the next line break, fits in `remaining` columns.
+fn fits(mut remaining: isize, doc: &Doc, rest: &[(usize, Mode, &Doc)]) -> bool {
+    let mut pending = vec![(Mode::Flat, doc)];
+    let mut rest = rest.iter().rev();
+
+    while remaining >= 0 {
+        let (mode, doc) = match pending.pop() {
+            Some(next) => next,
+            None => match rest.next() {
+                Some((_, mode, doc)) => (*mode, *doc),
+                None => return true,
+            },
+        };
+
+        match doc {
+            Doc::Text(_, width) => remaining -= *width as isize,
+            Doc::Line(flat) => match mode {
+                Mode::Flat => remaining -= flat.len() as isize,
+                Mode::Break => return true,
+            },
+            Doc::Nest(doc) | Doc::Group(doc) => pending.push((mode, doc)),
+            Doc::Concat(docs) => pending.extend(docs.iter().rev().map(|doc| (mode, doc))),
+            Doc::BreakParent if mode == Mode::Flat => return false,
+            Doc::BreakParent => {}
+        }
+    }
+
+    false
+}
+
+#[cfg(test)]
+mod layout_tests {
+    use super::*;
+
+    fn list(items: &[&str]) -> Doc {
+        let mut contents = vec![Doc::Line("")];
+        for (index, item) in items.iter().enumerate() {
+            if index > 0 {
+                contents.push(Doc::text(","));
+                contents.push(Doc::Line(" "));
+            }
+            contents.push(Doc::text(*item));
+        }
+
+        Doc::group(Doc::Concat(vec![
+            Doc::text("["),
+            Doc::nest(Doc::Concat(contents)),
+            Doc::Line(""),
+            Doc::text("]"),
+        ]))
+    }
+
+    fn rendered(doc: &Doc, options: &FormatOptions) -> String {
+        let mut output = String::new();
+        render(doc, options, &mut output).unwrap();
+
+        output
+    }
+
+    fn with_width(max_width: usize) -> FormatOptions {
+        FormatOptions {
+            max_width,
+            ..FormatOptions::default()
+        }
+    }
+
+    #[test]
+    fn it_keeps_a_group_flat_when_it_fits() {
+        let doc = list(&["1", "2", "3"]);
+
+        assert_eq!("[1, 2, 3]", rendered(&doc, &with_width(9)));
+    }
+
+    #[test]
+    fn it_breaks_a_group_that_does_not_fit() {
+        let doc = list(&["1", "2", "3"]);
+
+        assert_eq!("[\n  1,\n  2,\n  3\n]", rendered(&doc, &with_width(8)));
+    }
+
+    #[test]
+    fn it_counts_the_text_after_a_group() {
+        let doc = Doc::Concat(vec![list(&["1", "2"]), Doc::text(",")]);
+
+        assert_eq!("[\n  1,\n  2\n],", rendered(&doc, &with_width(6)));
+    }
+
+    #[test]
+    fn it_ignores_the_declared_width_of_colored_text() {
+        let doc = Doc::group(Doc::Concat(vec![
+            Doc::Text("\u{1b}[1m\"a\"\u{1b}[0m".to_string(), 3),
+            Doc::Line(" "),
+            Doc::text("1"),
+        ]));
+
+        assert_eq!("\u{1b}[1m\"a\"\u{1b}[0m 1", rendered(&doc, &with_width(5)));
+    }
+
+    #[test]
+    fn it_breaks_every_group_around_a_break_parent() {
+        let bracketed = |contents: Vec<Doc>| {
+            Doc::group(Doc::Concat(vec![
+                Doc::text("["),
+                Doc::nest(Doc::Concat(contents)),
+                Doc::Line(""),
+                Doc::text("]"),
+            ]))
+        };
+        let inner = bracketed(vec![Doc::Line(""), Doc::text("1"), Doc::BreakParent]);
+        let doc = bracketed(vec![Doc::Line(""), inner]);
+
+        assert_eq!("[\n  [\n    1\n  ]\n]", rendered(&doc, &with_width(80)));
+    }
+}
@@ -0,0 +1,61 @@
+/*
+jfmt
+Copyright (C) 2024 - Víctor Berga
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <http://www.gnu.org/licenses/>.
+*/
+
+//! A formatter for JSON, JSON5 and JSONC.
+//!
+//! [`format_with`] turns a document into formatted text in one call:
+//!
+//! ```
+//! use jfmt::{format_with, Dialect, FormatOptions};
+//!
+//! let options = FormatOptions {
+//!     input: Dialect::Json5,
+//!     ..FormatOptions::default()
+//! };
+//!
+//! assert_eq!("{\"a\": [1, 2]}", format_with("{a: [1, 2,]}", &options).unwrap());
+//! ```
+//!
+//! [`parse`] gives the [`Value`] tree instead, with the byte span of every
+//! value in the input, which [`format_value`] renders once it has been
+//! inspected or changed.
+
+pub mod compare;
+pub mod filter;
+mod formatter;
+mod layout;
+pub mod nodes;
+pub mod options;
+pub mod parser;
+pub mod pointer;
+pub mod query;
+// Input handling of the jfmt binary, which is not part of the library's API
+// and is only built for the command.
+#[cfg(feature = "cli")]
+#[doc(hidden)]
+pub mod records;
+pub mod sort;
+#[cfg(feature = "cli")]
+#[doc(hidden)]
+pub mod stream;
+pub mod theme;
+
+pub use formatter::{format_value, format_with, Error};
+pub use nodes::{FormatError, Node, Span, Value};
+pub use options::{Dialect, FormatOptions};
+pub use parser::{parse, ParseError};
@@ -16,55 +16,1041 @@ You should have received a copy of the GNU General Public License
 along with this program.  If not, see <http://www.gnu.org/licenses/>.
 */
 
-mod formatter;
-mod nodes;
-mod parser;
+mod diff;
+mod exit;
+mod input;
+mod output;
+#[cfg(test)]
+mod scratch;
+
+fn main() -> ExitCode {
+    let command = match Command::try_parse() {
+        Ok(command) => command,
+        Err(error) => {
+            let _ = error.print();
+            let status = if error.use_stderr() {
+                Status::Usage
+            } else {
+                Status::Success
+            };
 
-fn main() {
-    let command = Command::parse();
-    let json = &command.contents();
+            return status.into();
+        }
+    };
 
-    println!("{}", formatter::format(json));
+    let status = match &command.subcommand {
+        Some(Subcommand::Diff(diff)) => diff.run().map_or_else(report, |()| Status::Success),
+        None => run(&command),
+    };
+
+    status.into()
+}
+
+/// Formats every input, carrying on past the ones that fail. The status is
+/// the one of the first failure.
+fn run(command: &Command) -> Status {
+    let sources = match command.sources() {
+        Ok(sources) => sources,
+        Err(failure) => return report(failure),
+    };
+
+    let options = command.options();
+    let mut status = Status::Success;
+    for source in &sources {
+        if let Err(failure) = command.format(source, &options) {
+            let failed = report(failure);
+            if status == Status::Success {
+                status = failed;
+            }
+        }
+    }
+
+    status
+}
+
+fn report(failure: Failure) -> Status {
+    eprintln!("{}: {failure}", env!("CARGO_PKG_NAME"));
+    failure.status()
 }
 
 use clap::Parser;
-use clap_stdin::FileOrStdin;
+use diff::DiffStyle;
+use exit::{Failure, Status};
+use input::{InputError, InputErrorKind, Source};
+use jfmt::compare::{compare, CompareOptions};
+use jfmt::filter::Filter;
+use jfmt::nodes::{Array, Value};
+use jfmt::options::{Compact, Dialect, FormatOptions, Indent, Newline, NumberFormat};
+use jfmt::pointer::Pointer;
+use jfmt::query::Query;
+use jfmt::records::{self, ErrorPolicy, RecordError};
+use jfmt::sort::{KeyOrdering, KeySort};
+use jfmt::stream;
+use jfmt::theme::{ColorChoice, Theme};
+use std::io::{BufRead, IsTerminal, Write};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+/// Inputs at least this large are formatted as they are read, when the
+/// options allow it.
+const STREAM_THRESHOLD: u64 = 64 * 1024 * 1024;
 
 #[derive(Parser)]
 #[command(name = env!("CARGO_PKG_NAME"))]
 #[command(about = env!("CARGO_PKG_DESCRIPTION"))]
 #[command(version = env!("CARGO_PKG_VERSION"))]
+#[command(args_conflicts_with_subcommands = true, disable_help_subcommand = true)]
+#[command(group(clap::ArgGroup::new("sorting").args(["sort_keys", "key_order"]).multiple(true)))]
 struct Command {
-    #[clap(default_value = "-")]
-    input: FileOrStdin,
+    #[command(subcommand)]
+    subcommand: Option<Subcommand>,
+
+    /// Files or directories to format; `-` reads standard input
+    #[arg(value_name = "PATH", default_value = "-")]
+    inputs: Vec<PathBuf>,
+
+    /// Rewrite the files in place instead of printing them
+    #[arg(short, long, conflicts_with = "output")]
+    write: bool,
+
+    /// Write the result to this file instead of standard output
+    #[arg(short, long, value_name = "FILE")]
+    output: Option<PathBuf>,
+
+    /// Only report the inputs that are not formatted, and exit with status 3
+    #[arg(long, conflicts_with_all = ["write", "output"])]
+    check: bool,
+
+    /// Like --check, and also print the changes as a unified diff
+    #[arg(long, conflicts_with_all = ["write", "output"])]
+    diff: bool,
+
+    /// Syntax of the input; by default .json5 and .jsonc files are read as such
+    #[arg(long, value_enum, value_name = "FORMAT")]
+    input_format: Option<Dialect>,
+
+    /// Syntax of the output; the syntax of the input by default
+    #[arg(long, value_enum, value_name = "FORMAT")]
+    output_format: Option<Dialect>,
+
+    /// Format each line of the input as a separate document (NDJSON, JSON Lines)
+    #[arg(long, group = "records")]
+    ndjson: bool,
+
+    /// Read and write an RFC 7464 JSON text sequence (application/json-seq)
+    #[arg(long, group = "records")]
+    seq: bool,
+
+    /// Format any number of JSON values written one after the other
+    #[arg(long, conflicts_with = "records")]
+    multi: bool,
+
+    /// Format the input as it is read, in constant memory; inputs over 64 MiB always are
+    #[arg(long, conflicts_with_all = ["check", "diff", "records", "multi", "canonical", "sort_keys", "key_order"])]
+    stream: bool,
+
+    /// Print only the value at this JSON Pointer (RFC 6901), e.g. /spec/containers/0
+    #[arg(long, value_name = "POINTER", value_parser = Pointer::parse, conflicts_with_all = ["write", "check", "diff", "stream", "records", "multi"])]
+    pointer: Option<Pointer>,
+
+    /// Print the values a JSONPath query (RFC 9535) selects, as an array
+    #[arg(long, value_name = "QUERY", value_parser = Query::parse, conflicts_with_all = ["pointer", "write", "check", "diff", "stream", "records", "multi"])]
+    query: Option<Query>,
+
+    /// Print each value --query selects on a line of its own instead
+    #[arg(long, requires = "query")]
+    lines: bool,
+
+    /// Transform the input with a jq-style filter, e.g. '.items[] | {id, name: .meta.name}'
+    #[arg(short, long, value_name = "FILTER", value_parser = Filter::parse, conflicts_with_all = ["pointer", "query", "write", "check", "diff", "stream", "records", "multi"])]
+    filter: Option<Filter>,
+
+    /// What to do with NDJSON lines or sequence records that cannot be formatted
+    #[arg(long, value_enum, value_name = "POLICY", default_value_t = ErrorPolicy::Fail, requires = "records")]
+    on_error: ErrorPolicy,
+
+    /// Number of spaces used for each indentation level
+    #[arg(long, value_name = "N", default_value_t = 2)]
+    indent: usize,
+
+    /// Indent with tabs instead of spaces
+    #[arg(long, conflicts_with = "indent")]
+    tabs: bool,
+
+    /// Line ending used in the output
+    #[arg(long, value_enum, default_value_t = Newline::Lf)]
+    newline: Newline,
+
+    /// Print the smallest valid JSON on a single line
+    #[arg(long, value_enum, value_name = "STYLE", num_args = 0..=1, require_equals = true, default_missing_value = "tight")]
+    compact: Option<Compact>,
+
+    /// Keep arrays and objects on one line when they fit in this many columns
+    #[arg(long, value_name = "N", default_value_t = 80)]
+    max_width: usize,
+
+    /// How numbers are written
+    #[arg(long, value_enum, default_value_t = NumberFormat::Preserve)]
+    numbers: NumberFormat,
+
+    /// Sort object keys
+    #[arg(long, value_enum, value_name = "ORDERING", num_args = 0..=1, require_equals = true, default_missing_value = "lexical")]
+    sort_keys: Option<KeyOrdering>,
+
+    /// Keys printed before any other, in this order
+    #[arg(long, value_name = "KEYS", value_delimiter = ',')]
+    key_order: Vec<String>,
+
+    /// Only sort the keys of this many levels of objects, starting with the outermost
+    #[arg(long, value_name = "N", requires = "sorting", value_parser = clap::builder::RangedU64ValueParser::<usize>::new().range(1..))]
+    sort_depth: Option<usize>,
+
+    /// Print the RFC 8785 canonical form (JCS) used for hashing and signing
+    #[arg(long, conflicts_with_all = ["compact", "numbers", "sort_keys", "key_order"])]
+    canonical: bool,
+
+    /// Color theme: default, solarized, monochrome-bold or the path of a theme file
+    #[arg(long, value_name = "THEME", default_value = "default", value_parser = Theme::from_arg)]
+    theme: Theme,
+
+    /// When to color the output
+    #[arg(long, value_enum, value_name = "WHEN", default_value_t = ColorChoice::Auto)]
+    color: ColorChoice,
+}
+
+#[derive(clap::Subcommand)]
+enum Subcommand {
+    /// Compare two documents value by value and list what changed, by JSON Pointer
+    Diff(DiffCommand),
+}
+
+#[derive(clap::Args)]
+struct DiffCommand {
+    /// The document before the changes; `-` reads standard input
+    old: PathBuf,
+
+    /// The document after the changes; `-` reads standard input
+    new: PathBuf,
+
+    /// Objects with the same members are equal in any order
+    #[arg(long)]
+    ignore_key_order: bool,
+
+    /// Arrays with the same elements are equal in any order
+    #[arg(long)]
+    ignore_array_order: bool,
+
+    /// Syntax of both documents; by default .json5 and .jsonc files are read as such
+    #[arg(long, value_enum, value_name = "FORMAT")]
+    input_format: Option<Dialect>,
+
+    /// When to color the output
+    #[arg(long, value_enum, value_name = "WHEN", default_value_t = ColorChoice::Auto)]
+    color: ColorChoice,
 }
 
 impl Command {
-    fn contents(&self) -> String {
-        let input_clone = self.input.clone();
-        let contents = input_clone.contents();
+    fn sources(&self) -> Result<Vec<Source>, Failure> {
+        let sources = input::collect(&self.inputs)?;
+
+        if self.write && sources.contains(&Source::Stdin) {
+            return Err(Failure::Usage(
+                "--write cannot rewrite standard input".to_string(),
+            ));
+        }
+        if self.output.is_some() && sources.len() != 1 {
+            return Err(Failure::Usage(format!(
+                "--output needs exactly one input, got {}",
+                sources.len()
+            )));
+        }
+
+        Ok(sources)
+    }
+
+    fn format(&self, source: &Source, options: &FormatOptions) -> Result<(), Failure> {
+        let (input, output) = self.dialects(source);
+        let options = &FormatOptions {
+            input,
+            output,
+            ..options.clone()
+        };
+
+        if self.streams(source, options)? {
+            if let Some(reader) = input::open(source)? {
+                return self.format_stream(source, reader, options);
+            }
+            if self.stream {
+                return Err(Failure::Usage(format!(
+                    "--stream only reads UTF-8, and {} is not",
+                    source.name()
+                )));
+            }
+        }
+
+        let contents = input::read(source)?;
+        let formatted = if self.ndjson || self.seq || self.multi {
+            self.format_records(source, &contents, options)?
+        } else if let Some(filter) = &self.filter {
+            self.transform(source, contents.trim_end(), filter, options)?
+        } else if let Some(query) = self.query.as_ref().filter(|_| self.lines) {
+            self.query_lines(source, contents.trim_end(), query, options)?
+        } else {
+            let formatted = match (&self.pointer, &self.query) {
+                (Some(pointer), _) => self.select(source, contents.trim_end(), pointer, options)?,
+                (_, Some(query)) => self.query(source, contents.trim_end(), query, options)?,
+                _ => jfmt::format_with(contents.trim_end(), options)
+                    .map_err(|error| error.with_path(&source.name()))?,
+            };
+
+            // Canonical output is hashed byte for byte, so nothing is appended to it.
+            let terminator = if options.canonical {
+                ""
+            } else {
+                options.newline()
+            };
+            format!("{formatted}{terminator}")
+        };
+
+        if self.check || self.diff {
+            return self.check(source, &contents, &formatted);
+        }
+
+        let destination = match (&self.output, source) {
+            (Some(path), _) => path,
+            (None, Source::File(path)) if self.write => {
+                if formatted == contents {
+                    return Ok(());
+                }
+                path
+            }
+            _ => {
+                let mut stdout = std::io::stdout().lock();
+                stdout.write_all(formatted.as_bytes())?;
+                stdout.flush()?;
+
+                return Ok(());
+            }
+        };
+
+        output::write_atomic(destination, &formatted).map_err(|error| Failure::Write {
+            path: destination.display().to_string(),
+            error,
+        })
+    }
+
+    /// Whether to format `source` as it is read rather than parsing it first.
+    fn streams(&self, source: &Source, options: &FormatOptions) -> Result<bool, Failure> {
+        let json = options.input == Dialect::Json && options.output == Dialect::Json;
+        if self.stream {
+            return match json {
+                true => Ok(true),
+                false => Err(Failure::Usage(
+                    "--stream only reads and writes JSON".to_string(),
+                )),
+            };
+        }
+
+        let large = match source {
+            Source::File(path) => {
+                std::fs::metadata(path).is_ok_and(|metadata| metadata.len() >= STREAM_THRESHOLD)
+            }
+            Source::Stdin => false,
+        };
+        let whole_document = options.sort.is_some()
+            || options.canonical
+            || self.check
+            || self.diff
+            || self.ndjson
+            || self.seq
+            || self.multi
+            || self.pointer.is_some()
+            || self.query.is_some()
+            || self.filter.is_some();
+
+        Ok(large && json && !whole_document)
+    }
+
+    fn format_stream(
+        &self,
+        source: &Source,
+        reader: Box<dyn BufRead>,
+        options: &FormatOptions,
+    ) -> Result<(), Failure> {
+        let failure = |error, destination: Option<&Path>| match (error, destination) {
+            (stream::Error::Read(error), _) => Failure::Input(InputError {
+                path: source.name(),
+                kind: InputErrorKind::Io(error),
+            }),
+            (stream::Error::Encoding(offset), _) => Failure::Input(InputError {
+                path: source.name(),
+                kind: InputErrorKind::Encoding {
+                    encoding: input::Encoding::Utf8,
+                    offset,
+                },
+            }),
+            (stream::Error::Write(error), Some(path)) => Failure::Write {
+                path: path.display().to_string(),
+                error,
+            },
+            (stream::Error::Write(error), None) => Failure::Io(error),
+            (error, _) => Failure::Stream {
+                path: source.name(),
+                error,
+            },
+        };
+
+        let destination = match (&self.output, source) {
+            (Some(path), _) => path,
+            (None, Source::File(path)) if self.write => path,
+            _ => {
+                return stream::format(reader, std::io::stdout().lock(), options)
+                    .map_err(|error| failure(error, None))
+            }
+        };
+
+        output::write_atomic_with(destination, |file| stream::format(reader, file, options))
+            .map_err(|error| failure(error, Some(destination)))
+    }
+
+    fn format_records(
+        &self,
+        source: &Source,
+        contents: &str,
+        options: &FormatOptions,
+    ) -> Result<String, Failure> {
+        let failure = |error: RecordError| Failure::Record {
+            location: error.location(&source.name()),
+            error: error.error,
+        };
+        let reported = |error| {
+            report(failure(error));
+        };
+
+        if self.multi {
+            if options.input != Dialect::Json {
+                return Err(Failure::Usage(format!(
+                    "--multi only reads JSON, not {}",
+                    options.input
+                )));
+            }
+            records::format_values(contents, options)
+        } else if self.seq {
+            records::format_sequence(contents, options, self.on_error, reported)
+        } else {
+            records::format_lines(contents, options, self.on_error, reported)
+        }
+        .map_err(failure)
+    }
+
+    /// Formats the value that `pointer` addresses in `contents`.
+    fn select(
+        &self,
+        source: &Source,
+        contents: &str,
+        pointer: &Pointer,
+        options: &FormatOptions,
+    ) -> Result<String, Failure> {
+        let value = self.parse(source, contents, options)?;
+        let value = pointer.resolve(&value).map_err(|error| Failure::Pointer {
+            path: source.name(),
+            error,
+        })?;
+
+        Ok(jfmt::format_value(value.clone(), options).map_err(jfmt::Error::from)?)
+    }
+
+    /// Formats the values that `query` selects in `contents` as an array.
+    fn query(
+        &self,
+        source: &Source,
+        contents: &str,
+        query: &Query,
+        options: &FormatOptions,
+    ) -> Result<String, Failure> {
+        let value = self.parse(source, contents, options)?;
+        let matches = Array {
+            values: query.select(&value).into_iter().cloned().collect(),
+            ..Array::default()
+        };
+
+        Ok(jfmt::format_value(matches.into(), options).map_err(jfmt::Error::from)?)
+    }
+
+    /// Formats each value that `query` selects in `contents` on a line of its
+    /// own, compact unless another --compact style is asked for.
+    fn query_lines(
+        &self,
+        source: &Source,
+        contents: &str,
+        query: &Query,
+        options: &FormatOptions,
+    ) -> Result<String, Failure> {
+        let value = self.parse(source, contents, options)?;
+        let options = &FormatOptions {
+            compact: options.compact.or(Some(Compact::Tight)),
+            ..options.clone()
+        };
+
+        format_each(query.select(&value).into_iter().cloned(), options)
+    }
+
+    /// Formats each output of `filter` run on `contents`, one after the
+    /// other.
+    fn transform(
+        &self,
+        source: &Source,
+        contents: &str,
+        filter: &Filter,
+        options: &FormatOptions,
+    ) -> Result<String, Failure> {
+        let value = self.parse(source, contents, options)?;
+        let outputs = filter.apply(&value).map_err(|error| Failure::Filter {
+            path: source.name(),
+            error,
+        })?;
+
+        format_each(outputs, options)
+    }
+
+    /// Parses `contents` whole, naming the input in syntax errors.
+    fn parse<'a>(
+        &self,
+        source: &Source,
+        contents: &'a str,
+        options: &FormatOptions,
+    ) -> Result<Value<'a>, Failure> {
+        Ok(jfmt::parse(contents, options.input)
+            .map_err(|error| jfmt::Error::from(error).with_path(&source.name()))?)
+    }
+
+    fn check(&self, source: &Source, contents: &str, formatted: &str) -> Result<(), Failure> {
+        if contents == formatted {
+            return Ok(());
+        }
+
+        if self.diff {
+            let style = if self.colorize() {
+                DiffStyle::colored()
+            } else {
+                DiffStyle::default()
+            };
+            let mut stdout = std::io::stdout().lock();
+            stdout
+                .write_all(diff::unified(&source.name(), contents, formatted, &style).as_bytes())?;
+            stdout.flush()?;
+        }
+
+        Err(Failure::Unformatted(source.name()))
+    }
+
+    fn dialects(&self, source: &Source) -> (Dialect, Dialect) {
+        let input = self.input_format.unwrap_or(detect(source));
+
+        (input, self.output_format.unwrap_or(input))
+    }
+
+    fn options(&self) -> FormatOptions {
+        let (input, output) = self.dialects(&Source::Stdin);
+        let indent = if self.tabs {
+            Indent::Tabs
+        } else {
+            Indent::Spaces(self.indent)
+        };
+
+        FormatOptions {
+            input,
+            output,
+            indent,
+            newline: self.newline,
+            compact: self.compact,
+            max_width: self.max_width,
+            numbers: self.numbers,
+            sort: self.key_sort(),
+            canonical: self.canonical,
+            theme: self.colorize_json().then(|| self.theme.clone()),
+        }
+    }
+
+    fn colorize_json(&self) -> bool {
+        // Files always get plain JSON, and checks compare it with the input.
+        let plain = self.write || self.output.is_some() || self.check || self.diff;
+
+        !plain && self.colorize()
+    }
+
+    fn colorize(&self) -> bool {
+        let is_terminal = std::io::stdout().is_terminal();
+
+        self.color
+            .enabled(is_terminal, |name| std::env::var(name).ok())
+    }
+
+    fn key_sort(&self) -> Option<KeySort> {
+        if self.sort_keys.is_none() && self.key_order.is_empty() {
+            return None;
+        }
 
-        contents.unwrap_or("".to_string())
+        Some(KeySort {
+            ordering: self.sort_keys,
+            priority: self.key_order.clone(),
+            depth: self.sort_depth,
+        })
     }
 }
 
+impl DiffCommand {
+    /// Prints the changes from the old document to the new one, failing
+    /// when there are any.
+    fn run(&self) -> Result<(), Failure> {
+        let source = |path: &PathBuf| match path.as_os_str() == "-" {
+            true => Source::Stdin,
+            false => Source::File(path.clone()),
+        };
+        let (old, new) = (source(&self.old), source(&self.new));
+        if old == Source::Stdin && new == Source::Stdin {
+            return Err(Failure::Usage(
+                "only one document can be read from standard input".to_string(),
+            ));
+        }
+
+        // Values are printed in the syntax they were read in, which can
+        // write all of them, `Infinity` included.
+        let options = |source: &Source| {
+            let input = self.input_format.unwrap_or(detect(source));
+            FormatOptions {
+                input,
+                output: input,
+                ..FormatOptions::default()
+            }
+        };
+        let (old_options, new_options) = (options(&old), options(&new));
+        let (old_contents, new_contents) = (input::read(&old)?, input::read(&new)?);
+        let parse = |source: &Source, contents, options: &FormatOptions| {
+            jfmt::parse(contents, options.input)
+                .map_err(|error| jfmt::Error::from(error).with_path(&source.name()))
+        };
+        let old_value = parse(&old, old_contents.trim_end(), &old_options)?;
+        let new_value = parse(&new, new_contents.trim_end(), &new_options)?;
+
+        let compare_options = CompareOptions {
+            ignore_key_order: self.ignore_key_order,
+            ignore_array_order: self.ignore_array_order,
+        };
+        let changes = compare(&old_value, &new_value, &compare_options);
+        if changes.is_empty() {
+            return Ok(());
+        }
+
+        let is_terminal = std::io::stdout().is_terminal();
+        let style = match self
+            .color
+            .enabled(is_terminal, |name| std::env::var(name).ok())
+        {
+            true => DiffStyle::colored(),
+            false => DiffStyle::default(),
+        };
+        let listed = diff::changes(&changes, (&old_options, &new_options), &style)
+            .map_err(jfmt::Error::from)?;
+        let mut stdout = std::io::stdout().lock();
+        stdout.write_all(listed.as_bytes())?;
+        stdout.flush()?;
+
+        Err(Failure::Different {
+            old: old.name(),
+            new: new.name(),
+        })
+    }
+}
+
+/// The dialect of an input, told by the extension of its file.
+fn detect(source: &Source) -> Dialect {
+    let extension = match source {
+        Source::File(path) => path.extension().and_then(|extension| extension.to_str()),
+        Source::Stdin => None,
+    };
+
+    match extension {
+        Some("json5") => Dialect::Json5,
+        Some("jsonc") => Dialect::Jsonc,
+        _ => Dialect::Json,
+    }
+}
+
+/// Formats each value followed by a newline. Canonical values are only
+/// separated by newlines, so the last one ends like a single canonical
+/// document does.
+fn format_each<'a>(
+    values: impl IntoIterator<Item = Value<'a>>,
+    options: &FormatOptions,
+) -> Result<String, Failure> {
+    let mut formatted = String::new();
+    for value in values {
+        if options.canonical && !formatted.is_empty() {
+            formatted.push_str(options.newline());
+        }
+        formatted.push_str(&jfmt::format_value(value, options).map_err(jfmt::Error::from)?);
+        if !options.canonical {
+            formatted.push_str(options.newline());
+        }
+    }
+
+    Ok(formatted)
+}
+
 #[cfg(test)]
 mod command_tests {
     use super::*;
-    use std::fs::File;
-    use std::io::Write;
+    use crate::scratch::Scratch;
+
+    impl Scratch {
+        /// Formats the file `input` as jfmt does when given `args`.
+        fn format(&self, args: &[&str], input: &str) -> Result<(), Failure> {
+            let command = Command::parse_from(["test"].iter().chain(args));
+
+            command.format(&Source::File(self.path(input)), &command.options())
+        }
+    }
 
     #[test]
     fn test_file_or_stdin() {
-        let contents = "{\"foo\": \"bar\"}";
-        let temp_file_path = "temp_test_file.txt";
-        let mut temp_file = File::create(temp_file_path).unwrap();
-        writeln!(temp_file, "{contents}").unwrap();
+        let args = Command::parse_from(["test"]);
+        assert_eq!(vec![Source::Stdin], args.sources().unwrap());
+
+        let args = Command::parse_from(["test", "a.json", "-", "b.json"]);
+        let expected = vec![
+            Source::File(PathBuf::from("a.json")),
+            Source::Stdin,
+            Source::File(PathBuf::from("b.json")),
+        ];
+        assert_eq!(expected, args.sources().unwrap());
+    }
+
+    #[test]
+    fn test_missing_file() {
+        let args = Command::parse_from(["test", "missing_test_file.json"]);
+        let source = Source::File(PathBuf::from("missing_test_file.json"));
+        let error = args.format(&source, &args.options()).unwrap_err();
+
+        assert_eq!(Status::Io, error.status());
+        assert!(error.to_string().starts_with("missing_test_file.json: "));
+    }
+
+    #[test]
+    fn test_write_in_place() {
+        let scratch = Scratch::new("write");
+        scratch.write("data.json", "{\"foo\":[1,2]}\n");
+
+        let args = Command::parse_from(["test", "-w", scratch.directory().to_str().unwrap()]);
+        let sources = args.sources().unwrap();
+        for source in &sources {
+            args.format(source, &args.options()).unwrap();
+        }
+
+        assert_eq!(vec![Source::File(scratch.path("data.json"))], sources);
+        assert_eq!("{\"foo\": [1, 2]}\n", scratch.read("data.json"));
+    }
+
+    #[test]
+    fn test_output_file() {
+        let scratch = Scratch::new("output");
+        scratch.write("in.json", "[true]");
+        let output = scratch.arg("out.json");
+
+        let args = Command::parse_from(["test", "--color=always", "-o", &output]);
+        assert_eq!(None, args.options().theme);
+        scratch
+            .format(&["--color=always", "-o", &output], "in.json")
+            .unwrap();
+
+        assert_eq!("[true]\n", scratch.read("out.json"));
+    }
+
+    #[test]
+    fn test_check() {
+        let scratch = Scratch::new("check");
+        scratch.write("formatted.json", "{\"a\": 1}\n");
+        scratch.write("unformatted.json", "{\"a\":1}");
+
+        let args = Command::parse_from(["test", "--check", "--color=always"]);
+        assert_eq!(None, args.options().theme);
+        let passed = scratch.format(&["--check", "--color=always"], "formatted.json");
+        let failed = scratch.format(&["--check", "--color=always"], "unformatted.json");
+
+        assert!(passed.is_ok());
+        assert_eq!(Status::Unformatted, failed.unwrap_err().status());
+        assert_eq!("{\"a\":1}", scratch.read("unformatted.json"));
+        assert!(Command::try_parse_from(["test", "--diff", "-w", "a.json"]).is_err());
+    }
+
+    #[test]
+    fn test_dialect_options() {
+        let json5 = Source::File(PathBuf::from("config.json5"));
+        let jsonc = Source::File(PathBuf::from("tsconfig.jsonc"));
+
+        let args = Command::parse_from(["test"]);
+        assert_eq!(
+            (Dialect::Json, Dialect::Json),
+            args.dialects(&Source::Stdin)
+        );
+        assert_eq!((Dialect::Json5, Dialect::Json5), args.dialects(&json5));
+        assert_eq!((Dialect::Jsonc, Dialect::Jsonc), args.dialects(&jsonc));
+
+        let args = Command::parse_from(["test", "--output-format", "json"]);
+        assert_eq!((Dialect::Json5, Dialect::Json), args.dialects(&json5));
+
+        let args = Command::parse_from(["test", "--input-format", "json5"]);
+        assert_eq!(
+            (Dialect::Json5, Dialect::Json5),
+            args.dialects(&Source::Stdin)
+        );
+    }
+
+    #[test]
+    fn test_ndjson_options() {
+        let scratch = Scratch::new("ndjson");
+        scratch.write("log.ndjson", "{\"a\":1}\nnot json\n[]\n");
+
+        scratch
+            .format(&["--ndjson", "--on-error=skip", "-w"], "log.ndjson")
+            .unwrap();
+
+        assert_eq!("{\"a\": 1}\n[]\n", scratch.read("log.ndjson"));
+        assert!(Command::try_parse_from(["test", "--on-error=skip"]).is_err());
+    }
+
+    #[test]
+    fn test_record_options() {
+        let scratch = Scratch::new("records");
+        scratch.write("values.json", "{\"a\":1}[]");
+        scratch.write("values.seq", "\u{1E}{\"a\":1}\n\u{1E}[\n");
+
+        scratch.format(&["--multi", "-w"], "values.json").unwrap();
+        scratch
+            .format(&["--seq", "--on-error=passthrough", "-w"], "values.seq")
+            .unwrap();
+        let failure = scratch
+            .format(&["--multi", "--input-format", "json5"], "values.json")
+            .unwrap_err();
+
+        assert_eq!("{\"a\": 1}\n[]\n", scratch.read("values.json"));
+        assert_eq!("\u{1E}{\"a\": 1}\n\u{1E}[\n", scratch.read("values.seq"));
+        assert_eq!(Status::Usage, failure.status());
+        assert!(Command::try_parse_from(["test", "--ndjson", "--seq"]).is_err());
+        assert!(Command::try_parse_from(["test", "--multi", "--seq"]).is_err());
+        assert!(Command::try_parse_from(["test", "--multi", "--on-error=skip"]).is_err());
+    }
+
+    #[test]
+    fn test_stream_options() {
+        let scratch = Scratch::new("stream");
+        scratch.write("data.json", "{\"a\":[1,2],\n\"b\":{}}");
+
+        scratch
+            .format(&["--stream", "--max-width=0", "-w"], "data.json")
+            .unwrap();
+        let failure = scratch
+            .format(&["--stream", "--input-format", "json5"], "data.json")
+            .unwrap_err();
+
+        assert_eq!(
+            "{\n  \"a\": [\n    1,\n    2\n  ],\n  \"b\": {}\n}\n",
+            scratch.read("data.json")
+        );
+        assert_eq!(Status::Usage, failure.status());
+        assert!(Command::try_parse_from(["test", "--stream", "--sort-keys"]).is_err());
+        assert!(Command::try_parse_from(["test", "--stream", "--check"]).is_err());
+    }
+
+    #[test]
+    fn test_pointer_options() {
+        let scratch = Scratch::new("pointer");
+        scratch.write("in.json", "{\"items\": [{\"id\": 1}, {\"id\": 2}]}");
+        let output = scratch.arg("out.json");
+
+        scratch
+            .format(&["--pointer=/items/1", "-o", &output], "in.json")
+            .unwrap();
+        let failure = scratch
+            .format(&["--pointer=/items/2/id"], "in.json")
+            .unwrap_err();
+
+        assert_eq!("{\"id\": 2}\n", scratch.read("out.json"));
+        assert_eq!(Status::Inapplicable, failure.status());
+        assert!(Command::try_parse_from(["test", "--pointer=items"]).is_err());
+        assert!(Command::try_parse_from(["test", "--pointer=/items", "-w"]).is_err());
+    }
+
+    #[test]
+    fn test_query_options() {
+        let scratch = Scratch::new("query");
+        scratch.write(
+            "in.json",
+            "[{\"id\": 1, \"ok\": false}, {\"id\": 2, \"ok\": true}]",
+        );
+        let output = scratch.arg("out.json");
+
+        let query = |args: &[&str]| {
+            let args: Vec<_> = args.iter().copied().chain(["-o", &output]).collect();
+            scratch.format(&args, "in.json").unwrap();
+            scratch.read("out.json")
+        };
+
+        assert_eq!("[1]\n", query(&["--query=$[?@.ok == false].id"]));
+        assert_eq!(
+            "{\"id\":1,\"ok\":false}\n{\"id\":2,\"ok\":true}\n",
+            query(&["--query=$[*]", "--lines"])
+        );
+        assert!(Command::try_parse_from(["test", "--query=$[?@.* == 1]"]).is_err());
+        assert!(Command::try_parse_from(["test", "--query=$", "--pointer=/a"]).is_err());
+    }
+
+    #[test]
+    fn test_filter_options() {
+        let scratch = Scratch::new("filter");
+        scratch.write(
+            "in.json",
+            "{\"items\": [{\"id\": 1, \"ok\": false}, {\"id\": 2}]}",
+        );
+        let output = scratch.arg("out.json");
+
+        scratch
+            .format(
+                &[
+                    "-f",
+                    ".items[] | select(.ok == false) | {id}",
+                    "-o",
+                    &output,
+                ],
+                "in.json",
+            )
+            .unwrap();
+        let failure = scratch
+            .format(&["--filter=.items[0].id.x"], "in.json")
+            .unwrap_err();
+
+        assert_eq!("{\"id\": 1}\n", scratch.read("out.json"));
+        assert_eq!(Status::Inapplicable, failure.status());
+
+        let (each, whole) = (scratch.arg("each.json"), scratch.arg("whole.json"));
+        scratch
+            .format(&["-f", ".items[]", "--canonical", "-o", &each], "in.json")
+            .unwrap();
+        scratch
+            .format(&["-f", ".items", "--canonical", "-o", &whole], "in.json")
+            .unwrap();
+        assert_eq!(
+            "{\"id\":1,\"ok\":false}\n{\"id\":2}",
+            scratch.read("each.json")
+        );
+        assert_eq!(
+            "[{\"id\":1,\"ok\":false},{\"id\":2}]",
+            scratch.read("whole.json")
+        );
+        assert!(Command::try_parse_from(["test", "-f", ".a |"]).is_err());
+        assert!(Command::try_parse_from(["test", "-f", "sort"]).is_err());
+        assert!(Command::try_parse_from(["test", "-f", ".", "--query=$"]).is_err());
+    }
+
+    #[test]
+    fn test_diff_command() {
+        let scratch = Scratch::new("compare");
+        scratch.write("old.json", "{\"a\": [1, 2], \"b\": 1}");
+        scratch.write("new.json5", "{b: 1, a: [2, 1]}");
+        scratch.write("infinite.json5", "{b: Infinity, a: [2, 1]}");
+
+        let diff = |old: &str, new: &str, args: &[&str]| {
+            let paths = [scratch.arg(old), scratch.arg(new)];
+            let args = ["test", "diff"]
+                .into_iter()
+                .chain(paths.iter().map(String::as_str))
+                .chain(args.iter().copied());
+            match Command::parse_from(args).subcommand {
+                Some(Subcommand::Diff(diff)) => diff.run(),
+                None => unreachable!(),
+            }
+        };
+        let ordered = diff("old.json", "new.json5", &[]).unwrap_err();
+        let unordered = diff(
+            "old.json",
+            "new.json5",
+            &["--ignore-key-order", "--ignore-array-order"],
+        );
+        let json5 = diff("new.json5", "infinite.json5", &[]).unwrap_err();
+
+        assert_eq!(Status::Different, ordered.status());
+        assert!(unordered.is_ok());
+        assert_eq!(Status::Different, json5.status());
+        assert!(Command::try_parse_from(["test", "diff", "a.json"]).is_err());
+        assert!(Command::try_parse_from(["test", "diff", "a.json", "b.json", "--write"]).is_err());
+    }
+
+    #[test]
+    fn test_destination_errors() {
+        let args = Command::parse_from(["test", "--write"]);
+        assert_eq!(Status::Usage, args.sources().unwrap_err().status());
+
+        let args = Command::parse_from(["test", "-o", "out.json", "a.json", "b.json"]);
+        assert_eq!(Status::Usage, args.sources().unwrap_err().status());
+
+        let result = Command::try_parse_from(["test", "-w", "-o", "out.json", "a.json"]);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_indentation_options() {
+        let args = Command::parse_from(["test", "--indent", "4"]);
+        assert_eq!(Indent::Spaces(4), args.options().indent);
+
+        let args = Command::parse_from(["test", "--tabs"]);
+        assert_eq!(Indent::Tabs, args.options().indent);
+
+        let args = Command::parse_from(["test", "--color=never"]);
+        assert_eq!(FormatOptions::default(), args.options());
+    }
+
+    #[test]
+    fn test_compact_options() {
+        let args = Command::parse_from(["test", "--compact"]);
+        assert_eq!(Some(Compact::Tight), args.options().compact);
+
+        let args = Command::parse_from(["test", "--compact=spaced"]);
+        assert_eq!(Some(Compact::Spaced), args.options().compact);
+    }
+
+    #[test]
+    fn test_color_options() {
+        let args = Command::parse_from(["test", "--color=never"]);
+        assert_eq!(None, args.options().theme);
+
+        let args = Command::parse_from(["test", "--color=always", "--theme=solarized"]);
+        assert_eq!(Theme::built_in("solarized"), args.options().theme);
+    }
+
+    #[test]
+    fn test_sort_options() {
+        let args = Command::parse_from(["test"]);
+        assert_eq!(None, args.options().sort);
+
+        let args = Command::parse_from(["test", "--key-order", "name,version"]);
+        let expected = KeySort {
+            ordering: None,
+            priority: vec!["name".to_string(), "version".to_string()],
+            depth: None,
+        };
+        assert_eq!(Some(expected), args.options().sort);
 
-        let args = Command::parse_from(["test", temp_file_path]);
-        let input = args.contents();
-        assert_eq!(input, contents);
+        let args = Command::parse_from(["test", "--sort-keys=natural", "--sort-depth", "1"]);
+        let expected = KeySort {
+            ordering: Some(KeyOrdering::Natural),
+            priority: vec![],
+            depth: Some(1),
+        };
+        assert_eq!(Some(expected), args.options().sort);
 
-        std::fs::remove_file(temp_file_path).unwrap();
+        assert!(Command::try_parse_from(["test", "--sort-depth", "1"]).is_err());
+        assert!(Command::try_parse_from(["test", "--sort-keys", "--sort-depth", "0"]).is_err());
+        assert!(Command::try_parse_from(["test", "--key-order", "a", "--sort-depth", "2"]).is_ok());
+        assert!(Command::try_parse_from(["test", "--sort-keys", "--key-order", "a"]).is_ok());
     }
 }
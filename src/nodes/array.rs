@@ -16,10 +16,20 @@ You should have received a copy of the GNU General Public License
 along with this program.  If not, see <http://www.gnu.org/licenses/>.
 */
 
-use crate::nodes::{node::TAB_SPACES, Node};
+use crate::layout::Doc;
+use crate::nodes::{
+    comments_of, empty_doc, write_empty_compact, Comments, FormatError, Node, Span, ToDoc, Value,
+};
+use crate::options::{Compact, FormatOptions};
+use crate::sort::KeySort;
+use crate::theme::Token;
+use std::fmt::Write;
 
+#[derive(Clone, Debug, Default, PartialEq)]
 pub struct Array<'a> {
-    pub values: Vec<Box<dyn Node<'a> + 'a>>,
+    pub values: Vec<Value<'a>>,
+    pub span: Span,
+    pub comments: Option<Box<Comments<'a>>>,
 }
 
 impl Array<'_> {
@@ -27,40 +37,87 @@ impl Array<'_> {
         self.values.is_empty()
     }
 
-    fn format_children(&self, tabs: usize) -> String {
-        self.values
-            .iter()
-            .map(|value| {
-                format!(
-                    "{}{}",
-                    " ".repeat(tabs * TAB_SPACES),
-                    value.format_as_child(tabs)
-                )
-            })
-            .reduce(|acc, value| format!("{acc},\n{value}"))
-            .unwrap_or("".to_string())
+    fn children_doc(&self, options: &FormatOptions) -> Result<Doc, FormatError> {
+        let mut children = Vec::with_capacity(self.values.len() * 3);
+
+        for (index, value) in self.values.iter().enumerate() {
+            let comments = comments_of(value, options);
+
+            children.push(Doc::Line(if index > 0 { " " } else { "" }));
+            if let Some(comments) = comments {
+                children.push(Doc::BreakParent);
+                children.push(comments.leading_doc(options));
+            }
+            children.push(value.to_doc(options)?);
+            if index + 1 < self.values.len() {
+                children.push(Doc::token(",", Token::Punctuation, options));
+            }
+            if let Some(comments) = comments {
+                children.push(comments.trailing_doc(options));
+            }
+        }
+
+        Ok(Doc::Concat(children))
     }
 }
 
-impl<'a> Node<'a> for Array<'a> {
-    fn format_as_child(&self, tabs: usize) -> std::string::String {
+impl<'a> ToDoc for Array<'a> {
+    fn to_doc(&self, options: &FormatOptions) -> Result<Doc, FormatError> {
         if self.is_empty() {
-            return "[]".to_string();
+            return Ok(empty_doc("[]", self.comments.as_deref(), options));
         }
 
-        format!(
-            "[\n{contents}\n{spaces}]",
-            contents = self.format_children(tabs + 1),
-            spaces = " ".repeat(tabs * TAB_SPACES)
-        )
+        Ok(Doc::group(Doc::Concat(vec![
+            Doc::token("[", Token::Punctuation, options),
+            Doc::nest(self.children_doc(options)?),
+            Doc::Line(""),
+            Doc::token("]", Token::Punctuation, options),
+        ])))
     }
+}
 
-    fn format_as_root(&self) -> std::string::String {
+impl<'a> Node<'a> for Array<'a> {
+    fn write_compact(
+        &self,
+        compact: Compact,
+        options: &FormatOptions,
+        out: &mut dyn Write,
+    ) -> Result<(), FormatError> {
         if self.is_empty() {
-            return "[]".to_string();
+            return write_empty_compact("[]", self.comments.as_deref(), options, out);
+        }
+
+        let separator = options.paint(Token::Punctuation, compact.item_separator());
+
+        out.write_str(&options.paint(Token::Punctuation, "["))?;
+        for (index, value) in self.values.iter().enumerate() {
+            if index > 0 {
+                out.write_str(&separator)?;
+            }
+            value.write_compact(compact, options, out)?;
         }
+        out.write_str(&options.paint(Token::Punctuation, "]"))?;
 
-        format!("[\n{contents}\n]", contents = self.format_children(1))
+        Ok(())
+    }
+
+    fn write_canonical(&self, out: &mut dyn Write) -> Result<(), FormatError> {
+        out.write_char('[')?;
+        for (index, value) in self.values.iter().enumerate() {
+            if index > 0 {
+                out.write_char(',')?;
+            }
+            value.write_canonical(out)?;
+        }
+        out.write_char(']')?;
+
+        Ok(())
+    }
+
+    fn sort_keys(&mut self, sort: &KeySort, depth: usize) {
+        for value in self.values.iter_mut() {
+            value.sort_keys(sort, depth);
+        }
     }
 }
 
@@ -68,12 +125,23 @@ impl<'a> Node<'a> for Array<'a> {
 mod array_tests {
     use super::*;
     use crate::nodes::{Boolean, Null};
+    use crate::options::{Indent, Newline};
+
+    fn expanded() -> FormatOptions {
+        FormatOptions {
+            max_width: 0,
+            ..FormatOptions::default()
+        }
+    }
 
     #[test]
     fn it_formats_empty_array() {
-        let array = Array { values: vec![] };
+        let array = Array {
+            values: vec![],
+            ..Array::default()
+        };
         let expected = "[]";
-        let result = array.format_as_root();
+        let result = array.format_as_root(&expanded()).unwrap();
 
         assert_eq!(expected, result);
     }
@@ -81,10 +149,11 @@ mod array_tests {
     #[test]
     fn it_formats_an_array_of_nulls() {
         let array = Array {
-            values: vec![Box::new(Null {})],
+            values: vec![Value::from(Null::default())],
+            ..Array::default()
         };
         let expected = "[\n  null\n]";
-        let result = array.format_as_root();
+        let result = array.format_as_root(&expanded()).unwrap();
 
         assert_eq!(expected, result);
     }
@@ -92,13 +161,15 @@ mod array_tests {
     #[test]
     fn it_formats_a_multidimensional_array_of_nulls() {
         let first_level_array = Array {
-            values: vec![Box::new(Null {})],
+            values: vec![Value::from(Null::default())],
+            ..Array::default()
         };
         let root_array = Array {
-            values: vec![Box::new(Null {}), Box::new(first_level_array)],
+            values: vec![Value::from(Null::default()), Value::from(first_level_array)],
+            ..Array::default()
         };
         let expected = "[\n  null,\n  [\n    null\n  ]\n]";
-        let result = root_array.format_as_root();
+        let result = root_array.format_as_root(&expanded()).unwrap();
 
         assert_eq!(expected, result);
     }
@@ -107,12 +178,19 @@ mod array_tests {
     fn it_formats_array_of_booleans() {
         let array = Array {
             values: vec![
-                Box::new(Boolean { value: true }),
-                Box::new(Boolean { value: false }),
+                Value::from(Boolean {
+                    value: true,
+                    ..Boolean::default()
+                }),
+                Value::from(Boolean {
+                    value: false,
+                    ..Boolean::default()
+                }),
             ],
+            ..Array::default()
         };
         let expected = "[\n  true,\n  false\n]";
-        let result = array.format_as_root();
+        let result = array.format_as_root(&expanded()).unwrap();
 
         assert_eq!(expected, result);
     }
@@ -121,15 +199,157 @@ mod array_tests {
     fn it_formats_array_of_multidimensaionl_booleans() {
         let first_level_array = Array {
             values: vec![
-                Box::new(Boolean { value: true }),
-                Box::new(Boolean { value: false }),
+                Value::from(Boolean {
+                    value: true,
+                    ..Boolean::default()
+                }),
+                Value::from(Boolean {
+                    value: false,
+                    ..Boolean::default()
+                }),
             ],
+            ..Array::default()
         };
         let root_array = Array {
-            values: vec![Box::new(Null {}), Box::new(first_level_array)],
+            values: vec![Value::from(Null::default()), Value::from(first_level_array)],
+            ..Array::default()
         };
         let expected = "[\n  null,\n  [\n    true,\n    false\n  ]\n]";
-        let result = root_array.format_as_root();
+        let result = root_array.format_as_root(&expanded()).unwrap();
+
+        assert_eq!(expected, result);
+    }
+
+    #[test]
+    fn it_formats_array_with_four_spaces_and_crlf() {
+        let first_level_array = Array {
+            values: vec![Value::from(Null::default())],
+            ..Array::default()
+        };
+        let root_array = Array {
+            values: vec![Value::from(Null::default()), Value::from(first_level_array)],
+            ..Array::default()
+        };
+        let options = FormatOptions {
+            indent: Indent::Spaces(4),
+            newline: Newline::Crlf,
+            ..expanded()
+        };
+        let expected = "[\r\n    null,\r\n    [\r\n        null\r\n    ]\r\n]";
+        let result = root_array.format_as_root(&options).unwrap();
+
+        assert_eq!(expected, result);
+    }
+
+    #[test]
+    fn it_formats_array_with_tabs() {
+        let first_level_array = Array {
+            values: vec![Value::from(Boolean {
+                value: true,
+                ..Boolean::default()
+            })],
+            ..Array::default()
+        };
+        let root_array = Array {
+            values: vec![Value::from(first_level_array)],
+            ..Array::default()
+        };
+        let options = FormatOptions {
+            indent: Indent::Tabs,
+            ..expanded()
+        };
+        let expected = "[\n\t[\n\t\ttrue\n\t]\n]";
+        let result = root_array.format_as_root(&options).unwrap();
+
+        assert_eq!(expected, result);
+    }
+
+    #[test]
+    fn it_formats_compact_arrays() {
+        let first_level_array = Array {
+            values: vec![
+                Value::from(Boolean {
+                    value: true,
+                    ..Boolean::default()
+                }),
+                Value::from(Boolean {
+                    value: false,
+                    ..Boolean::default()
+                }),
+            ],
+            ..Array::default()
+        };
+        let root_array = Array {
+            values: vec![Value::from(Null::default()), Value::from(first_level_array)],
+            ..Array::default()
+        };
+
+        assert_eq!(
+            "[null,[true,false]]",
+            root_array
+                .format_compact(Compact::Tight, &FormatOptions::default())
+                .unwrap()
+        );
+        assert_eq!(
+            "[null, [true, false]]",
+            root_array
+                .format_compact(Compact::Spaced, &FormatOptions::default())
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn it_keeps_short_arrays_on_one_line() {
+        let first_level_array = Array {
+            values: vec![
+                Value::from(Boolean {
+                    value: true,
+                    ..Boolean::default()
+                }),
+                Value::from(Boolean {
+                    value: false,
+                    ..Boolean::default()
+                }),
+            ],
+            ..Array::default()
+        };
+        let root_array = Array {
+            values: vec![Value::from(Null::default()), Value::from(first_level_array)],
+            ..Array::default()
+        };
+        let expected = "[null, [true, false]]";
+        let result = root_array
+            .format_as_root(&FormatOptions::default())
+            .unwrap();
+
+        assert_eq!(expected, result);
+    }
+
+    #[test]
+    fn it_breaks_only_the_arrays_that_do_not_fit() {
+        let first_level_array = Array {
+            values: vec![
+                Value::from(Boolean {
+                    value: true,
+                    ..Boolean::default()
+                }),
+                Value::from(Boolean {
+                    value: false,
+                    ..Boolean::default()
+                }),
+            ],
+            ..Array::default()
+        };
+        let root_array = Array {
+            values: vec![Value::from(Null::default()), Value::from(first_level_array)],
+            ..Array::default()
+        };
+        let options = FormatOptions {
+            max_width: 16,
+            ..FormatOptions::default()
+        };
+        let expected = "[\n  null,\n  [true, false]\n]";
+        let result = root_array.format_as_root(&options).unwrap();
 
         assert_eq!(expected, result);
     }
@@ -16,19 +16,37 @@ You should have received a copy of the GNU General Public License
 along with this program.  If not, see <http://www.gnu.org/licenses/>.
 */
 
-use super::Node;
-
-pub struct Boolean {
+use super::{Comments, FormatError, Node, Span, ToDoc};
+use crate::layout::Doc;
+use crate::options::{Compact, FormatOptions};
+use crate::theme::Token;
+use std::fmt::Write;
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Boolean<'a> {
     pub value: bool,
+    pub span: Span,
+    pub comments: Option<Box<Comments<'a>>>,
+}
+
+impl<'a> ToDoc for Boolean<'a> {
+    fn to_doc(&self, options: &FormatOptions) -> Result<Doc, FormatError> {
+        Ok(Doc::token(self.value.to_string(), Token::Boolean, options))
+    }
 }
 
-impl<'a> Node<'a> for Boolean {
-    fn format_as_child(&self, _tabs: usize) -> std::string::String {
-        self.value.to_string()
+impl<'a> Node<'a> for Boolean<'a> {
+    fn write_compact(
+        &self,
+        _compact: Compact,
+        options: &FormatOptions,
+        out: &mut dyn Write,
+    ) -> Result<(), FormatError> {
+        Ok(out.write_str(&options.paint(Token::Boolean, &self.value.to_string()))?)
     }
 
-    fn format_as_root(&self) -> std::string::String {
-        self.format_as_child(0)
+    fn write_canonical(&self, out: &mut dyn Write) -> Result<(), FormatError> {
+        Ok(write!(out, "{}", self.value)?)
     }
 }
 
@@ -37,37 +55,53 @@ mod boolean_tests {
     use super::*;
 
     #[test]
-    fn test_formats_true_boolean_as_child() {
-        let value = Boolean { value: true };
+    fn test_formats_true_boolean_compact() {
+        let value = Boolean {
+            value: true,
+            ..Boolean::default()
+        };
         let expected = "true";
-        let result = value.format_as_child(0);
+        let result = value
+            .format_compact(Compact::Tight, &FormatOptions::default())
+            .unwrap();
 
         assert_eq!(expected, result);
     }
 
     #[test]
-    fn test_formats_false_boolean_as_child() {
-        let value = Boolean { value: false };
+    fn test_formats_false_boolean_compact() {
+        let value = Boolean {
+            value: false,
+            ..Boolean::default()
+        };
         let expected = "false";
-        let result = value.format_as_child(0);
+        let result = value
+            .format_compact(Compact::Tight, &FormatOptions::default())
+            .unwrap();
 
         assert_eq!(expected, result);
     }
 
     #[test]
     fn test_formats_true_boolean_as_root() {
-        let value = Boolean { value: true };
+        let value = Boolean {
+            value: true,
+            ..Boolean::default()
+        };
         let expected = "true";
-        let result = value.format_as_root();
+        let result = value.format_as_root(&FormatOptions::default()).unwrap();
 
         assert_eq!(expected, result);
     }
 
     #[test]
     fn test_formats_false_boolean_as_root() {
-        let value = Boolean { value: false };
+        let value = Boolean {
+            value: false,
+            ..Boolean::default()
+        };
         let expected = "false";
-        let result = value.format_as_root();
+        let result = value.format_as_root(&FormatOptions::default()).unwrap();
 
         assert_eq!(expected, result);
     }
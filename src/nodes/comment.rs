@@ -0,0 +1,163 @@
+/*
+jfmt
+Copyright (C) 2024 - Víctor Berga
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <http://www.gnu.org/licenses/>.
+*/
+
+use super::{FormatError, Value};
+use crate::layout::Doc;
+use crate::options::{Dialect, FormatOptions};
+use crate::theme::Token;
+use std::borrow::Cow;
+use std::fmt::Write;
+
+/// Comments of a JSONC document that belong to one value, written as they
+/// appear in the input, `//` and `/* */` included.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Comments<'a> {
+    /// Comments on the lines before the value, or before the key of a member.
+    pub leading: Vec<&'a str>,
+    /// Comments after the value on the same line.
+    pub trailing: Vec<&'a str>,
+    /// Comments on the lines after the last value of a container.
+    pub after: Vec<&'a str>,
+    /// Comments between the brackets of an empty container.
+    pub inside: Vec<&'a str>,
+}
+
+impl Comments<'_> {
+    pub fn is_empty(&self) -> bool {
+        self.leading.is_empty()
+            && self.trailing.is_empty()
+            && self.after.is_empty()
+            && self.inside.is_empty()
+    }
+
+    /// Each leading comment followed by a line break.
+    pub fn leading_doc(&self, options: &FormatOptions) -> Doc {
+        let mut docs = Vec::with_capacity(self.leading.len() * 2);
+
+        for comment in &self.leading {
+            docs.push(Doc::token(*comment, Token::Comment, options));
+            docs.push(Doc::Line(""));
+        }
+
+        Doc::Concat(docs)
+    }
+
+    /// Trailing comments on the same line, then the comments on the lines
+    /// after.
+    pub fn trailing_doc(&self, options: &FormatOptions) -> Doc {
+        let mut docs = Vec::with_capacity((self.trailing.len() + self.after.len()) * 2);
+
+        for comment in &self.trailing {
+            docs.push(Doc::text(" "));
+            docs.push(Doc::token(*comment, Token::Comment, options));
+        }
+        for comment in &self.after {
+            docs.push(Doc::Line(""));
+            docs.push(Doc::token(*comment, Token::Comment, options));
+        }
+
+        Doc::Concat(docs)
+    }
+}
+
+/// Comments that have to be printed around `value`, which are none when the
+/// output is strict JSON.
+pub fn comments_of<'n, 'a>(
+    value: &'n Value<'a>,
+    options: &FormatOptions,
+) -> Option<&'n Comments<'a>> {
+    if options.output == Dialect::Json {
+        return None;
+    }
+
+    value.comments()
+}
+
+/// Comments inside an empty container, which are none when the output is
+/// strict JSON.
+fn inside<'c>(comments: Option<&'c Comments>, options: &FormatOptions) -> &'c [&'c str] {
+    match comments {
+        Some(comments) if options.output != Dialect::Json => &comments.inside,
+        _ => &[],
+    }
+}
+
+/// An empty container written with `brackets`, keeping the comments inside
+/// it between them. Line comments put the closing bracket on a line of its
+/// own.
+pub fn empty_doc(brackets: &str, comments: Option<&Comments>, options: &FormatOptions) -> Doc {
+    let inside = inside(comments, options);
+    if inside.is_empty() {
+        return Doc::token(brackets, Token::Punctuation, options);
+    }
+
+    let mut docs = Vec::with_capacity(inside.len() * 3);
+    for comment in inside {
+        if comment.starts_with("//") {
+            docs.push(Doc::BreakParent);
+        }
+        docs.push(Doc::Line(" "));
+        docs.push(Doc::token(*comment, Token::Comment, options));
+    }
+
+    let (open, close) = brackets.split_at(1);
+    Doc::group(Doc::Concat(vec![
+        Doc::token(open, Token::Punctuation, options),
+        Doc::nest(Doc::Concat(docs)),
+        Doc::Line(" "),
+        Doc::token(close, Token::Punctuation, options),
+    ]))
+}
+
+/// Like [`empty_doc`], on a single line.
+pub fn write_empty_compact(
+    brackets: &str,
+    comments: Option<&Comments>,
+    options: &FormatOptions,
+    out: &mut dyn Write,
+) -> Result<(), FormatError> {
+    let (open, close) = brackets.split_at(1);
+
+    out.write_str(&options.paint(Token::Punctuation, open))?;
+    for comment in inside(comments, options) {
+        out.write_char(' ')?;
+        out.write_str(&options.paint(Token::Comment, &inline(comment)?))?;
+    }
+    if !inside(comments, options).is_empty() {
+        out.write_char(' ')?;
+    }
+    out.write_str(&options.paint(Token::Punctuation, close))?;
+
+    Ok(())
+}
+
+/// A comment that can be followed by more tokens on its line: line comments
+/// become block comments, unless they hold a `*/` that would end them early.
+pub fn inline<'c>(comment: &'c str) -> Result<Cow<'c, str>, FormatError> {
+    let Some(text) = comment.strip_prefix("//") else {
+        return Ok(Cow::Borrowed(comment));
+    };
+    if text.contains("*/") {
+        return Err(FormatError::CommentNotInline(comment.to_string()));
+    }
+
+    Ok(Cow::Owned(format!(
+        "/*{}{text} */",
+        if text.starts_with(' ') { "" } else { " " }
+    )))
+}
@@ -0,0 +1,77 @@
+/*
+jfmt
+Copyright (C) 2024 - Víctor Berga
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <http://www.gnu.org/licenses/>.
+*/
+
+use std::fmt;
+
+/// Reasons a parsed document cannot be rendered with the requested options.
+#[derive(Debug, PartialEq)]
+pub enum FormatError {
+    /// A number that does not fit in an `f64` had to be normalized.
+    NumberOutOfRange(std::string::String),
+    /// `Infinity` or `NaN`, which only JSON5 can represent.
+    NonFiniteNumber(std::string::String),
+    /// A string escape that does not encode a Unicode scalar value, such as
+    /// an unpaired surrogate.
+    InvalidEscape(std::string::String),
+    /// A line comment holding `*/`, which cannot be turned into a block
+    /// comment to be written on a single line.
+    CommentNotInline(std::string::String),
+    /// An object with two members of the same name, which canonical JSON
+    /// cannot hold. The name is unescaped.
+    DuplicateName(std::string::String),
+    /// The sink the output goes to refused it.
+    Write,
+}
+
+impl fmt::Display for FormatError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FormatError::NumberOutOfRange(lexeme) => {
+                write!(
+                    f,
+                    "number {lexeme} is out of range and cannot be normalized"
+                )
+            }
+            FormatError::NonFiniteNumber(lexeme) => {
+                write!(f, "number {lexeme} cannot be written as JSON")
+            }
+            FormatError::InvalidEscape(escape) => {
+                write!(f, "escape {escape} is not a valid Unicode character")
+            }
+            FormatError::CommentNotInline(comment) => {
+                write!(f, "comment {comment} cannot be written on a single line")
+            }
+            FormatError::DuplicateName(name) => {
+                write!(
+                    f,
+                    "name {} is used by more than one member and cannot be written as canonical JSON",
+                    super::escape_canonical(name)
+                )
+            }
+            FormatError::Write => write!(f, "the output could not be written"),
+        }
+    }
+}
+
+impl std::error::Error for FormatError {}
+
+impl From<fmt::Error> for FormatError {
+    fn from(_: fmt::Error) -> Self {
+        FormatError::Write
+    }
+}
@@ -18,16 +18,26 @@ along with this program.  If not, see <http://www.gnu.org/licenses/>.
 
 mod array;
 mod boolean;
+mod comment;
+mod error;
 mod node;
 mod null;
 mod number;
 mod object;
 mod string;
+mod value;
 
 pub use array::Array;
 pub use boolean::Boolean;
+pub use comment::Comments;
+pub(crate) use comment::{comments_of, empty_doc, inline, write_empty_compact};
+pub use error::FormatError;
 pub use node::Node;
+pub(crate) use node::ToDoc;
 pub use null::Null;
 pub use number::Number;
 pub use object::Object;
+pub use string::escape_canonical;
 pub use string::String;
+pub(crate) use string::{escape_char, unescape, unescaped};
+pub use value::{Span, Value};
@@ -16,10 +16,76 @@ You should have received a copy of the GNU General Public License
 along with this program.  If not, see <http://www.gnu.org/licenses/>.
 */
 
-pub const TAB_SPACES: usize = 2;
+use super::FormatError;
+use crate::layout::{self, Doc};
+use crate::options::{Compact, FormatOptions};
+use crate::sort::KeySort;
+use std::fmt::Write;
 
-pub trait Node<'a> {
-    fn format_as_child(&self, tabs: usize) -> std::string::String;
+/// A parsed value. Nodes write their output into any [`Write`] sink, so a
+/// document is rendered in a single buffer however deep it is nested.
+pub trait Node<'a>: ToDoc {
+    /// Writes the node as a whole document, keeping containers on one line
+    /// when they fit in `max_width`.
+    fn write_as_root(
+        &self,
+        options: &FormatOptions,
+        out: &mut dyn Write,
+    ) -> Result<(), FormatError> {
+        Ok(layout::render(&self.to_doc(options)?, options, out)?)
+    }
 
-    fn format_as_root(&self) -> std::string::String;
+    /// Writes the node on a single line.
+    fn write_compact(
+        &self,
+        compact: Compact,
+        options: &FormatOptions,
+        out: &mut dyn Write,
+    ) -> Result<(), FormatError>;
+
+    /// Writes the node as described by RFC 8785, the JSON Canonicalization
+    /// Scheme.
+    fn write_canonical(&self, out: &mut dyn Write) -> Result<(), FormatError>;
+
+    fn format_as_root(&self, options: &FormatOptions) -> Result<std::string::String, FormatError> {
+        let mut output = std::string::String::new();
+        self.write_as_root(options, &mut output)?;
+
+        Ok(output)
+    }
+
+    fn format_compact(
+        &self,
+        compact: Compact,
+        options: &FormatOptions,
+    ) -> Result<std::string::String, FormatError> {
+        let mut output = std::string::String::new();
+        self.write_compact(compact, options, &mut output)?;
+
+        Ok(output)
+    }
+
+    fn format_canonical(&self) -> Result<std::string::String, FormatError> {
+        let mut output = std::string::String::new();
+        self.write_canonical(&mut output)?;
+
+        Ok(output)
+    }
+
+    /// Reorders the members of this node and of its descendants, `depth`
+    /// being the number of objects that enclose it.
+    fn sort_keys(&mut self, _sort: &KeySort, _depth: usize) {}
+}
+
+pub(crate) use layout_of::ToDoc;
+
+mod layout_of {
+    use super::*;
+
+    /// The layout of a node, which `Node` needs but the library keeps to
+    /// itself. The trait is public only within this private module, so no
+    /// type outside of the crate can implement `Node`.
+    pub trait ToDoc {
+        fn to_doc(&self, options: &FormatOptions) -> Result<Doc, FormatError>;
+    }
 }
@@ -16,17 +16,36 @@ You should have received a copy of the GNU General Public License
 along with this program.  If not, see <http://www.gnu.org/licenses/>.
 */
 
-use crate::nodes::Node;
+use crate::layout::Doc;
+use crate::nodes::{Comments, FormatError, Node, Span, ToDoc};
+use crate::options::{Compact, FormatOptions};
+use crate::theme::Token;
+use std::fmt::Write;
 
-pub struct Null {}
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Null<'a> {
+    pub span: Span,
+    pub comments: Option<Box<Comments<'a>>>,
+}
+
+impl<'a> ToDoc for Null<'a> {
+    fn to_doc(&self, options: &FormatOptions) -> Result<Doc, FormatError> {
+        Ok(Doc::token("null", Token::Null, options))
+    }
+}
 
-impl<'a> Node<'a> for Null {
-    fn format_as_child(&self, _tabs: usize) -> std::string::String {
-        "null".to_string()
+impl<'a> Node<'a> for Null<'a> {
+    fn write_compact(
+        &self,
+        _compact: Compact,
+        options: &FormatOptions,
+        out: &mut dyn Write,
+    ) -> Result<(), FormatError> {
+        Ok(out.write_str(&options.paint(Token::Null, "null"))?)
     }
 
-    fn format_as_root(&self) -> std::string::String {
-        self.format_as_child(0)
+    fn write_canonical(&self, out: &mut dyn Write) -> Result<(), FormatError> {
+        Ok(out.write_str("null")?)
     }
 }
 
@@ -35,17 +54,19 @@ mod null_tests {
     use super::*;
 
     #[test]
-    fn test_formats_null_as_child() {
-        let null = Null {};
-        let result = null.format_as_child(0);
+    fn test_formats_null_compact() {
+        let null = Null::default();
+        let result = null
+            .format_compact(Compact::Tight, &FormatOptions::default())
+            .unwrap();
 
         assert_eq!("null", result);
     }
 
     #[test]
     fn test_formats_null_as_root() {
-        let null = Null {};
-        let result = null.format_as_root();
+        let null = Null::default();
+        let result = null.format_as_root(&FormatOptions::default()).unwrap();
 
         assert_eq!("null", result);
     }
@@ -16,19 +16,398 @@ You should have received a copy of the GNU General Public License
 along with this program.  If not, see <http://www.gnu.org/licenses/>.
 */
 
-use super::Node;
+use super::{Comments, FormatError, Node, Span, ToDoc};
+use crate::layout::Doc;
+use crate::options::{Compact, Dialect, FormatOptions, NumberFormat};
+use crate::theme::Token;
+use std::borrow::Cow;
+use std::cmp::Ordering;
+use std::fmt::Write;
 
-pub struct Number {
-    pub value: f64,
+/// A number kept exactly as it was spelled in the input.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Number<'a> {
+    pub lexeme: Cow<'a, str>,
+    pub span: Span,
+    pub comments: Option<Box<Comments<'a>>>,
 }
 
-impl<'a> Node<'a> for Number {
-    fn format_as_child(&self, _tabs: usize) -> std::string::String {
-        self.value.to_string()
+impl<'a> Number<'a> {
+    /// Returns the number as a float, or `None` when it is outside of the
+    /// range that `f64` can represent, whether too large or too small.
+    pub fn value(&self) -> Option<f64> {
+        let lexeme = self.json_lexeme().ok()?;
+        let value = lexeme.parse::<f64>().ok()?;
+
+        // A number too close to zero parses as zero.
+        let mantissa = lexeme.split(['e', 'E']).next().unwrap_or_default();
+        let underflow = value == 0.0 && mantissa.contains(|c| matches!(c, '1'..='9'));
+
+        (value.is_finite() && !underflow).then_some(value)
+    }
+
+    /// Compares the values of two numbers exactly, however they are spelled
+    /// and however many digits they have. Infinities are below or above
+    /// every other number, and `NaN` compares with nothing.
+    pub fn compare(&self, other: &Number) -> Option<Ordering> {
+        match (self.decimal(), other.decimal()) {
+            (Some(a), Some(b)) => Some(a.cmp(&b)),
+            _ => self.infinity().partial_cmp(&other.infinity()),
+        }
+    }
+
+    /// The number as its sign, significant digits and the position of the
+    /// decimal point, or `None` for `Infinity` and `NaN`.
+    fn decimal(&self) -> Option<Decimal> {
+        let lexeme = self.json_lexeme().ok()?;
+        let (negative, magnitude) = match lexeme.strip_prefix('-') {
+            Some(magnitude) => (true, magnitude),
+            None => (false, &*lexeme),
+        };
+        let (mantissa, exponent) = magnitude.split_once(['e', 'E']).unwrap_or((magnitude, "0"));
+        // Exponents too long for an i64 are far beyond any other number.
+        let exponent = exponent
+            .parse::<i64>()
+            .unwrap_or(match exponent.starts_with('-') {
+                true => i64::MIN / 2,
+                false => i64::MAX / 2,
+            });
+
+        let (integer, fraction) = mantissa.split_once('.').unwrap_or((mantissa, ""));
+        let digits = format!("{integer}{fraction}");
+        let leading = digits.len() - digits.trim_start_matches('0').len();
+        let significant = digits.trim_matches('0');
+
+        Some(Decimal {
+            negative: negative && !significant.is_empty(),
+            point: exponent.saturating_add(integer.len() as i64 - leading as i64),
+            digits: significant.to_string(),
+        })
+    }
+
+    /// The value of the number, spelled the same for every number it compares
+    /// equal to. `NaN` is spelled as it is written.
+    pub(crate) fn exact(&self) -> std::string::String {
+        match self.decimal() {
+            Some(Decimal { digits, .. }) if digits.is_empty() => "0".to_string(),
+            Some(Decimal {
+                negative,
+                digits,
+                point,
+            }) => format!("{}0.{digits}e{point}", if negative { "-" } else { "" }),
+            None if self.infinity().is_nan() => self.lexeme.to_string(),
+            None => self.infinity().to_string(),
+        }
+    }
+
+    /// Infinities as floats, and any finite number as zero.
+    fn infinity(&self) -> f64 {
+        match self.is_non_finite() {
+            true => self
+                .lexeme
+                .trim_start_matches('+')
+                .parse()
+                .unwrap_or(f64::NAN),
+            false => 0.0,
+        }
+    }
+
+    fn finite(&self) -> Result<f64, FormatError> {
+        // Reports `Infinity` and `NaN` before anything is out of range.
+        self.json_lexeme()?;
+
+        self.value()
+            .ok_or_else(|| FormatError::NumberOutOfRange(self.lexeme.to_string()))
+    }
+
+    /// The exact digits of a number, which has to be in the range of `f64`
+    /// like every normalized one.
+    fn finite_decimal(&self) -> Result<Decimal, FormatError> {
+        self.finite()?;
+
+        Ok(self.decimal().expect("finite numbers have digits"))
+    }
+
+    /// `Infinity` and `NaN`, with or without a sign.
+    fn is_non_finite(&self) -> bool {
+        let magnitude = self.lexeme.trim_start_matches(['+', '-']);
+
+        magnitude == "Infinity" || magnitude == "NaN"
+    }
+
+    /// The number spelled as JSON. JSON5 numbers may have a plus sign, a
+    /// hexadecimal value, or a decimal point without digits on one side, and
+    /// those are rewritten.
+    pub fn json_lexeme(&self) -> Result<Cow<'_, str>, FormatError> {
+        if self.is_non_finite() {
+            return Err(FormatError::NonFiniteNumber(self.lexeme.to_string()));
+        }
+
+        let (sign, magnitude) = match self.lexeme.strip_prefix('-') {
+            Some(magnitude) => ("-", magnitude),
+            None => ("", self.lexeme.trim_start_matches('+')),
+        };
+
+        let magnitude = match magnitude
+            .strip_prefix("0x")
+            .or_else(|| magnitude.strip_prefix("0X"))
+        {
+            Some(digits) => hexadecimal_to_decimal(digits),
+            None => {
+                let (mantissa, exponent) = match magnitude.find(['e', 'E']) {
+                    Some(index) => magnitude.split_at(index),
+                    None => (magnitude, ""),
+                };
+                let mantissa = mantissa.strip_suffix('.').unwrap_or(mantissa);
+                let zero = if mantissa.starts_with('.') { "0" } else { "" };
+
+                format!("{zero}{mantissa}{exponent}")
+            }
+        };
+
+        let json = format!("{sign}{magnitude}");
+        if json == self.lexeme {
+            Ok(Cow::Borrowed(&self.lexeme))
+        } else {
+            Ok(Cow::Owned(json))
+        }
+    }
+
+    fn normalize(
+        &self,
+        format: NumberFormat,
+        dialect: Dialect,
+    ) -> Result<Cow<'_, str>, FormatError> {
+        if dialect == Dialect::Json5 && (format == NumberFormat::Preserve || self.is_non_finite()) {
+            return Ok(Cow::Borrowed(&self.lexeme));
+        }
+
+        let text = match format {
+            NumberFormat::Preserve => return self.json_lexeme(),
+            NumberFormat::Canonical => canonical(self.finite()?),
+            NumberFormat::Decimal => decimal(&self.finite_decimal()?),
+            NumberFormat::Scientific => scientific(&self.finite_decimal()?),
+        };
+
+        Ok(Cow::Owned(text))
+    }
+}
+
+/// A finite number written as `±0.digits × 10^point`, with no leading or
+/// trailing zeros in the digits. Zero has no digits.
+#[derive(Eq)]
+struct Decimal {
+    negative: bool,
+    digits: std::string::String,
+    point: i64,
+}
+
+impl Ord for Decimal {
+    fn cmp(&self, other: &Decimal) -> Ordering {
+        let sign = |decimal: &Decimal| match (decimal.digits.is_empty(), decimal.negative) {
+            (true, _) => 0,
+            (false, true) => -1,
+            (false, false) => 1,
+        };
+
+        match sign(self).cmp(&sign(other)) {
+            Ordering::Equal if sign(self) != 0 => {
+                // Digits start with a non-zero one, so they compare as text.
+                let magnitude = self
+                    .point
+                    .cmp(&other.point)
+                    .then_with(|| self.digits.cmp(&other.digits));
+                match self.negative {
+                    true => magnitude.reverse(),
+                    false => magnitude,
+                }
+            }
+            order => order,
+        }
+    }
+}
+
+impl PartialEq for Decimal {
+    fn eq(&self, other: &Decimal) -> bool {
+        self.cmp(other).is_eq()
+    }
+}
+
+impl PartialOrd for Decimal {
+    fn partial_cmp(&self, other: &Decimal) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Converts hexadecimal digits to decimal ones, exactly and whatever their
+/// length.
+fn hexadecimal_to_decimal(digits: &str) -> std::string::String {
+    // Decimal digits of the value, least significant first.
+    let mut decimal = vec![0u8];
+
+    for digit in digits.chars() {
+        let mut carry = digit.to_digit(16).unwrap() as u8;
+        for place in decimal.iter_mut() {
+            let total = *place * 16 + carry;
+            *place = total % 10;
+            carry = total / 10;
+        }
+        while carry > 0 {
+            decimal.push(carry % 10);
+            carry /= 10;
+        }
+    }
+
+    decimal
+        .iter()
+        .rev()
+        .map(|digit| (b'0' + digit) as char)
+        .collect()
+}
+
+/// Splits a finite value into its shortest round-trip digits and the
+/// position of the decimal point relative to them, so that
+/// `value = 0.digits × 10^point`.
+fn decompose(value: f64) -> (std::string::String, i32) {
+    let (digits, exponent) = split_exponent(&format!("{:e}", value.abs()));
+
+    (break_tie(value.abs(), digits, exponent), exponent + 1)
+}
+
+fn split_exponent(formatted: &str) -> (std::string::String, i32) {
+    let (mantissa, exponent) = formatted.split_once('e').unwrap();
+
+    (mantissa.replace('.', ""), exponent.parse().unwrap())
+}
+
+/// When the value lies exactly halfway between the shortest digits and a
+/// neighbour of the same length that also round-trips, ECMAScript picks the
+/// even one, while Rust may pick either.
+fn break_tie(value: f64, digits: std::string::String, exponent: i32) -> std::string::String {
+    let last = digits.as_bytes()[digits.len() - 1] - b'0';
+    if last.is_multiple_of(2) || last == 9 {
+        return digits;
+    }
+
+    // A double never has more than 767 significant decimal digits.
+    let (exact, exact_exponent) = split_exponent(&format!("{:.767e}", value));
+    let exact = exact.trim_end_matches('0');
+
+    let prefix = &digits[..digits.len() - 1];
+    for neighbour in [last - 1, last + 1] {
+        let candidate = format!("{prefix}{neighbour}");
+        let midpoint = format!("{prefix}{}5", last.min(neighbour));
+        let parsed: f64 = format!("0.{candidate}e{}", exponent + 1).parse().unwrap();
+
+        if parsed == value && exact_exponent == exponent && exact == midpoint {
+            return candidate;
+        }
+    }
+
+    digits
+}
+
+fn sign(value: f64) -> &'static str {
+    if value < 0.0 {
+        "-"
+    } else {
+        ""
+    }
+}
+
+fn exponent(point: i32) -> std::string::String {
+    let exponent = point - 1;
+    let sign = if exponent < 0 { "-" } else { "+" };
+
+    format!("e{sign}{}", exponent.abs())
+}
+
+fn with_exponent(digits: &str, point: i32) -> std::string::String {
+    let (first, rest) = digits.split_at(1);
+    let fraction = if rest.is_empty() {
+        "".to_string()
+    } else {
+        format!(".{rest}")
+    };
+
+    format!("{first}{fraction}{}", exponent(point))
+}
+
+fn without_exponent(digits: &str, point: i32) -> std::string::String {
+    let length = digits.len() as i32;
+
+    if point >= length {
+        format!("{digits}{}", "0".repeat((point - length) as usize))
+    } else if point > 0 {
+        let (integer, fraction) = digits.split_at(point as usize);
+        format!("{integer}.{fraction}")
+    } else {
+        format!("0.{}{digits}", "0".repeat(-point as usize))
+    }
+}
+
+/// Shortest round-trip representation, spelled the way ECMAScript's
+/// `Number.prototype.toString` does.
+fn canonical(value: f64) -> std::string::String {
+    if value == 0.0 {
+        return "0".to_string();
     }
 
-    fn format_as_root(&self) -> std::string::String {
-        self.format_as_child(0)
+    let (digits, point) = decompose(value);
+    let text = if -6 < point && point <= 21 {
+        without_exponent(&digits, point)
+    } else {
+        with_exponent(&digits, point)
+    };
+
+    format!("{}{text}", sign(value))
+}
+
+/// The number without an exponent, with all of its digits.
+fn decimal(number: &Decimal) -> std::string::String {
+    if number.digits.is_empty() {
+        return "0".to_string();
+    }
+
+    let text = without_exponent(&number.digits, number.point as i32);
+    format!("{}{text}", if number.negative { "-" } else { "" })
+}
+
+/// The number with one digit before the point and an exponent, with all of
+/// its digits.
+fn scientific(number: &Decimal) -> std::string::String {
+    if number.digits.is_empty() {
+        return "0e+0".to_string();
+    }
+
+    let text = with_exponent(&number.digits, number.point as i32);
+    format!("{}{text}", if number.negative { "-" } else { "" })
+}
+
+impl<'a> ToDoc for Number<'a> {
+    fn to_doc(&self, options: &FormatOptions) -> Result<Doc, FormatError> {
+        Ok(Doc::token(
+            self.normalize(options.numbers, options.output)?,
+            Token::Number,
+            options,
+        ))
+    }
+}
+
+impl<'a> Node<'a> for Number<'a> {
+    fn write_compact(
+        &self,
+        _compact: Compact,
+        options: &FormatOptions,
+        out: &mut dyn Write,
+    ) -> Result<(), FormatError> {
+        let number = self.normalize(options.numbers, options.output)?;
+
+        Ok(out.write_str(&options.paint(Token::Number, &number))?)
+    }
+
+    fn write_canonical(&self, out: &mut dyn Write) -> Result<(), FormatError> {
+        Ok(out.write_str(&self.normalize(NumberFormat::Canonical, Dialect::Json)?)?)
     }
 }
 
@@ -37,29 +416,286 @@ mod number_tests {
     use super::*;
 
     #[test]
-    fn it_formats_positive_value_as_child() {
-        let number = Number { value: 2.0 };
+    fn it_formats_positive_value_compact() {
+        let number = Number {
+            lexeme: "2".into(),
+            ..Number::default()
+        };
         let expected = "2";
-        let result = number.format_as_child(0);
+        let result = number
+            .format_compact(Compact::Tight, &FormatOptions::default())
+            .unwrap();
 
         assert_eq!(expected, result);
     }
 
     #[test]
-    fn it_formats_negative_value_as_child() {
-        let number = Number { value: -2.0 };
+    fn it_formats_negative_value_compact() {
+        let number = Number {
+            lexeme: "-2".into(),
+            ..Number::default()
+        };
         let expected = "-2";
-        let result = number.format_as_child(0);
+        let result = number
+            .format_compact(Compact::Tight, &FormatOptions::default())
+            .unwrap();
 
         assert_eq!(expected, result);
     }
 
     #[test]
     fn it_formats_positive_value_as_root() {
-        let number = Number { value: 2.0 };
+        let number = Number {
+            lexeme: "2".into(),
+            ..Number::default()
+        };
         let expected = "2";
-        let result = number.format_as_root();
+        let result = number.format_as_root(&FormatOptions::default()).unwrap();
 
         assert_eq!(expected, result);
     }
+
+    #[test]
+    fn it_preserves_the_original_spelling() {
+        for lexeme in ["1.0", "5e3", "-0", "1E+2", "12345678901234567891", "1e400"] {
+            let number = Number {
+                lexeme: lexeme.into(),
+                ..Number::default()
+            };
+            let result = number.format_as_root(&FormatOptions::default()).unwrap();
+
+            assert_eq!(lexeme, result);
+        }
+    }
+
+    #[test]
+    fn it_parses_the_value_when_in_range() {
+        assert_eq!(
+            Some(5000.0),
+            Number {
+                lexeme: "5e3".into(),
+                ..Number::default()
+            }
+            .value()
+        );
+        assert_eq!(
+            None,
+            Number {
+                lexeme: "1e400".into(),
+                ..Number::default()
+            }
+            .value()
+        );
+    }
+
+    #[test]
+    fn it_compares_numbers_exactly() {
+        let number = |lexeme: &'static str| Number {
+            lexeme: lexeme.into(),
+            ..Number::default()
+        };
+        let cases = [
+            (
+                "12345678901234567891",
+                "12345678901234567892",
+                Some(Ordering::Less),
+            ),
+            ("1.5e1", "15", Some(Ordering::Equal)),
+            ("-0.0", "0", Some(Ordering::Equal)),
+            ("0x10", "+016.", Some(Ordering::Equal)),
+            ("0.012", ".0120e0", Some(Ordering::Equal)),
+            ("-2", "-10", Some(Ordering::Greater)),
+            ("1e-400", "0", Some(Ordering::Greater)),
+            ("1e400", "Infinity", Some(Ordering::Less)),
+            ("-Infinity", "-1e400", Some(Ordering::Less)),
+            ("NaN", "NaN", None),
+        ];
+
+        for (a, b, expected) in cases {
+            assert_eq!(expected, number(a).compare(&number(b)), "{a} {b}");
+        }
+    }
+
+    fn normalized(lexeme: &str, numbers: NumberFormat) -> Result<std::string::String, FormatError> {
+        let options = FormatOptions {
+            numbers,
+            ..FormatOptions::default()
+        };
+
+        Number {
+            lexeme: lexeme.into(),
+            ..Number::default()
+        }
+        .format_as_root(&options)
+    }
+
+    #[test]
+    fn it_formats_canonical_numbers() {
+        let cases = [
+            ("1.0", "1"),
+            ("5e3", "5000"),
+            ("-0", "0"),
+            ("0.10", "0.1"),
+            ("1E30", "1e+30"),
+            ("2e-3", "0.002"),
+            ("0.000000000000000000000000001", "1e-27"),
+            ("333333333.33333329", "333333333.3333333"),
+            ("123456789012345678901", "123456789012345680000"),
+        ];
+
+        for (lexeme, expected) in cases {
+            assert_eq!(
+                Ok(expected.to_string()),
+                normalized(lexeme, NumberFormat::Canonical)
+            );
+        }
+    }
+
+    #[test]
+    fn it_formats_decimal_numbers() {
+        let cases = [
+            ("1.5e3", "1500"),
+            ("-2.5E-4", "-0.00025"),
+            ("1e22", "10000000000000000000000"),
+            ("12345678901234567891", "12345678901234567891"),
+            (
+                "0.1000000000000000055511151231257827e1",
+                "1.000000000000000055511151231257827",
+            ),
+        ];
+
+        for (lexeme, expected) in cases {
+            assert_eq!(
+                Ok(expected.to_string()),
+                normalized(lexeme, NumberFormat::Decimal)
+            );
+        }
+    }
+
+    #[test]
+    fn it_formats_scientific_numbers() {
+        let cases = [
+            ("1500", "1.5e+3"),
+            ("-0.00025", "-2.5e-4"),
+            ("7", "7e+0"),
+            ("0.0", "0e+0"),
+            ("-12345678901234567891", "-1.2345678901234567891e+19"),
+        ];
+
+        for (lexeme, expected) in cases {
+            assert_eq!(
+                Ok(expected.to_string()),
+                normalized(lexeme, NumberFormat::Scientific)
+            );
+        }
+    }
+
+    #[test]
+    fn it_refuses_to_normalize_out_of_range_numbers() {
+        let expected = Err(FormatError::NumberOutOfRange("1e400".to_string()));
+
+        assert_eq!(expected, normalized("1e400", NumberFormat::Canonical));
+        assert_eq!(
+            Err(FormatError::NumberOutOfRange("1e-400".to_string())),
+            normalized("1e-400", NumberFormat::Canonical)
+        );
+        assert_eq!(
+            Ok("0".to_string()),
+            normalized("0.0e-400", NumberFormat::Canonical)
+        );
+        assert_eq!(
+            Ok("1e400".to_string()),
+            normalized("1e400", NumberFormat::Preserve)
+        );
+    }
+
+    #[test]
+    fn it_serializes_numbers_like_ecmascript() {
+        // Samples from RFC 8785, Appendix B.
+        let cases = [
+            (0x0000000000000000, "0"),
+            (0x8000000000000000, "0"),
+            (0x0000000000000001, "5e-324"),
+            (0x8000000000000001, "-5e-324"),
+            (0x7fefffffffffffff, "1.7976931348623157e+308"),
+            (0xffefffffffffffff, "-1.7976931348623157e+308"),
+            (0x4340000000000000, "9007199254740992"),
+            (0xc340000000000000, "-9007199254740992"),
+            (0x4430000000000000, "295147905179352830000"),
+            (0x44b52d02c7e14af5, "9.999999999999997e+22"),
+            (0x44b52d02c7e14af6, "1e+23"),
+            (0x44b52d02c7e14af7, "1.0000000000000001e+23"),
+            (0x444b1ae4d6e2ef4e, "999999999999999700000"),
+            (0x444b1ae4d6e2ef4f, "999999999999999900000"),
+            (0x444b1ae4d6e2ef50, "1e+21"),
+            (0x3eb0c6f7a0b5ed8c, "9.999999999999997e-7"),
+            (0x3eb0c6f7a0b5ed8d, "0.000001"),
+            (0x41b3de4355555553, "333333333.3333332"),
+            (0x41b3de4355555554, "333333333.33333325"),
+            (0x41b3de4355555555, "333333333.3333333"),
+            (0x41b3de4355555556, "333333333.3333334"),
+            (0x41b3de4355555557, "333333333.33333343"),
+            (0xbecbf647612f3696, "-0.0000033333333333333333"),
+            (0x43143ff3c1cb0959, "1424953923781206.2"),
+        ];
+
+        for (bits, expected) in cases {
+            assert_eq!(expected, canonical(f64::from_bits(bits)));
+        }
+    }
+
+    #[test]
+    fn it_spells_json5_numbers_as_json() {
+        let cases = [
+            ("+1", "1"),
+            ("0x1F", "31"),
+            ("-0XfF", "-255"),
+            ("0xFFFFFFFFFFFFFFFFFFFF", "1208925819614629174706175"),
+            (".5", "0.5"),
+            ("5.", "5"),
+            ("-5.e3", "-5e3"),
+            ("1.5", "1.5"),
+        ];
+
+        for (lexeme, expected) in cases {
+            assert_eq!(
+                Ok(expected.into()),
+                Number {
+                    lexeme: lexeme.into(),
+                    ..Number::default()
+                }
+                .json_lexeme()
+            );
+        }
+        assert_eq!(
+            Some(31.0),
+            Number {
+                lexeme: "0x1F".into(),
+                ..Number::default()
+            }
+            .value()
+        );
+    }
+
+    #[test]
+    fn it_keeps_json5_numbers_in_json5_output() {
+        let options = FormatOptions {
+            output: Dialect::Json5,
+            ..FormatOptions::default()
+        };
+
+        for lexeme in ["0x1F", "+Infinity", "NaN", ".5"] {
+            assert_eq!(
+                Ok(lexeme.to_string()),
+                Number {
+                    lexeme: lexeme.into(),
+                    ..Number::default()
+                }
+                .format_as_root(&options)
+            );
+        }
+
+        let expected = Err(FormatError::NonFiniteNumber("-Infinity".to_string()));
+        assert_eq!(expected, normalized("-Infinity", NumberFormat::Preserve));
+    }
 }
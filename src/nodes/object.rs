@@ -16,13 +16,53 @@ You should have received a copy of the GNU General Public License
 along with this program.  If not, see <http://www.gnu.org/licenses/>.
 */
 
-use crate::nodes::node::TAB_SPACES;
+use crate::layout::Doc;
+use crate::options::{Compact, Dialect, FormatOptions};
+use crate::sort::KeySort;
+use crate::theme::Token;
 
-use super::Node;
-use colored::Colorize;
+use super::string::{escape_canonical, unescape, unescaped};
+use super::{
+    comments_of, empty_doc, write_empty_compact, Comments, FormatError, Node, Span, ToDoc, Value,
+};
+use std::borrow::Cow;
+use std::fmt::Write;
 
+#[derive(Clone, Debug, Default, PartialEq)]
 pub struct Object<'a> {
-    pub members: Vec<(&'a str, Box<dyn Node<'a> + 'a>)>,
+    /// Members in input order, keyed by their name with JSON escapes.
+    pub members: Vec<(Cow<'a, str>, Value<'a>)>,
+    pub span: Span,
+    pub comments: Option<Box<Comments<'a>>>,
+}
+
+/// Spells a member name for the output: JSON5 leaves names that are plain
+/// identifiers unquoted.
+fn key(name: &str, options: &FormatOptions) -> std::string::String {
+    let mut chars = name.chars();
+    let identifier = chars
+        .next()
+        .is_some_and(|c| c.is_alphabetic() || c == '$' || c == '_')
+        && chars.all(|c| c.is_alphabetic() || c.is_ascii_digit() || c == '$' || c == '_');
+
+    if options.output == Dialect::Json5 && identifier {
+        name.to_string()
+    } else {
+        format!("\"{name}\"")
+    }
+}
+
+impl<'a> Object<'a> {
+    /// The value of the member called `name`, comparing names without their
+    /// escapes. A repeated name finds its last member, the one most parsers
+    /// keep.
+    pub fn get(&self, name: &str) -> Option<&Value<'a>> {
+        self.members
+            .iter()
+            .rev()
+            .find(|(key, _)| unescaped(key) == name)
+            .map(|(_, value)| value)
+    }
 }
 
 impl Object<'_> {
@@ -30,41 +70,115 @@ impl Object<'_> {
         self.members.is_empty()
     }
 
-    fn format_children(&self, tabs: usize) -> String {
-        self.members
-            .iter()
-            .map(|member| {
-                let indendation = " ".repeat(tabs * TAB_SPACES);
-                let attribute = format!("\"{}\"", member.0).bright_purple().bold();
-                let value = member.1.format_as_child(tabs);
+    fn children_doc(&self, options: &FormatOptions) -> Result<Doc, FormatError> {
+        let mut children = Vec::with_capacity(self.members.len() * 3);
 
-                format!("{indendation}{attribute}: {value}")
-            })
-            .reduce(|acc, member| format!("{acc},\n{member}"))
-            .unwrap_or("".to_string())
+        for (index, member) in self.members.iter().enumerate() {
+            let comments = comments_of(&member.1, options);
+
+            children.push(Doc::Line(if index > 0 { " " } else { "" }));
+            if let Some(comments) = comments {
+                // Comments keep the object broken, so line comments still
+                // end where their line does.
+                children.push(Doc::BreakParent);
+                children.push(comments.leading_doc(options));
+            }
+            children.push(Doc::Concat(vec![
+                Doc::token(key(&member.0, options), Token::Key, options),
+                Doc::token(":", Token::Punctuation, options),
+                Doc::text(" "),
+                member.1.to_doc(options)?,
+            ]));
+            if index + 1 < self.members.len() {
+                children.push(Doc::token(",", Token::Punctuation, options));
+            }
+            if let Some(comments) = comments {
+                children.push(comments.trailing_doc(options));
+            }
+        }
+
+        Ok(Doc::Concat(children))
     }
 }
 
-impl<'a> Node<'a> for Object<'a> {
-    fn format_as_child(&self, tabs: usize) -> std::string::String {
+impl<'a> ToDoc for Object<'a> {
+    fn to_doc(&self, options: &FormatOptions) -> Result<Doc, FormatError> {
         if self.is_empty() {
-            return "{}".to_string();
+            return Ok(empty_doc("{}", self.comments.as_deref(), options));
         }
 
-        format!(
-            "{{\n{contents}\n{spaces}}}",
-            contents = self.format_children(tabs + 1),
-            spaces = " ".repeat(tabs * TAB_SPACES)
-        )
+        Ok(Doc::group(Doc::Concat(vec![
+            Doc::token("{", Token::Punctuation, options),
+            Doc::nest(self.children_doc(options)?),
+            Doc::Line(""),
+            Doc::token("}", Token::Punctuation, options),
+        ])))
     }
+}
 
-    fn format_as_root(&self) -> std::string::String {
+impl<'a> Node<'a> for Object<'a> {
+    fn write_compact(
+        &self,
+        compact: Compact,
+        options: &FormatOptions,
+        out: &mut dyn Write,
+    ) -> Result<(), FormatError> {
         if self.is_empty() {
-            return "{}".to_string();
+            return write_empty_compact("{}", self.comments.as_deref(), options, out);
+        }
+
+        let item_separator = options.paint(Token::Punctuation, compact.item_separator());
+        let key_separator = options.paint(Token::Punctuation, compact.key_separator());
+
+        out.write_str(&options.paint(Token::Punctuation, "{"))?;
+        for (index, (name, value)) in self.members.iter().enumerate() {
+            if index > 0 {
+                out.write_str(&item_separator)?;
+            }
+            value.write_leading_comments(options, out)?;
+            out.write_str(&options.paint(Token::Key, &key(name, options)))?;
+            out.write_str(&key_separator)?;
+            value.write_compact_after_leading(compact, options, out)?;
+        }
+        out.write_str(&options.paint(Token::Punctuation, "}"))?;
+
+        Ok(())
+    }
+
+    fn write_canonical(&self, out: &mut dyn Write) -> Result<(), FormatError> {
+        let mut members = self
+            .members
+            .iter()
+            .map(|member| Ok((unescape(&member.0)?, &member.1)))
+            .collect::<Result<Vec<_>, FormatError>>()?;
+        members.sort_by(|a, b| a.0.encode_utf16().cmp(b.0.encode_utf16()));
+        if let Some(pair) = members.windows(2).find(|pair| pair[0].0 == pair[1].0) {
+            return Err(FormatError::DuplicateName(pair[0].0.clone()));
+        }
+
+        out.write_char('{')?;
+        for (index, (name, value)) in members.iter().enumerate() {
+            if index > 0 {
+                out.write_char(',')?;
+            }
+            out.write_str(&escape_canonical(name))?;
+            out.write_char(':')?;
+            value.write_canonical(out)?;
         }
+        out.write_char('}')?;
 
-        let contents = self.format_children(1);
-        format!("{{\n{contents}\n}}")
+        Ok(())
+    }
+
+    fn sort_keys(&mut self, sort: &KeySort, depth: usize) {
+        if sort.applies_at(depth) {
+            self.members
+                .sort_by(|a, b| sort.compare(&unescaped(&a.0), &unescaped(&b.0)));
+        }
+
+        for member in self.members.iter_mut() {
+            member.1.sort_keys(sort, depth + 1);
+        }
     }
 }
 
@@ -72,47 +186,299 @@ impl<'a> Node<'a> for Object<'a> {
 mod object_tests {
     use super::*;
 
-    use crate::nodes::{Number, String};
+    use crate::nodes::{Null, Number, String};
+    use crate::sort::KeyOrdering;
+    use crate::theme::{Style, Theme};
+
+    fn expanded() -> FormatOptions {
+        FormatOptions {
+            max_width: 0,
+            ..FormatOptions::default()
+        }
+    }
+
+    fn with_colored_keys(options: FormatOptions) -> FormatOptions {
+        let theme = Theme {
+            key: Style::parse("bold bright-purple").unwrap(),
+            ..Theme::default()
+        };
+
+        FormatOptions {
+            theme: Some(theme),
+            ..options
+        }
+    }
 
     #[test]
     fn it_formats_an_empty_object() {
-        let object = Object { members: vec![] };
+        let object = Object {
+            members: vec![],
+            ..Object::default()
+        };
         let expected = "{}";
-        let result = object.format_as_root();
+        let result = object.format_as_root(&expanded()).unwrap();
 
         assert_eq!(expected, result);
     }
 
     #[test]
     fn it_formats_an_object() {
-        colored::control::set_override(true);
         let object = Object {
-            members: vec![("member", Box::new(String { value: "value" }))],
+            members: vec![(
+                "member".into(),
+                Value::from(String {
+                    value: "value".into(),
+                    ..String::default()
+                }),
+            )],
+            ..Object::default()
         };
         let expected = "{\n  \u{1b}[1;95m\"member\"\u{1b}[0m: \"value\"\n}";
-        let result = object.format_as_root();
-        colored::control::unset_override();
+        let result = object
+            .format_as_root(&with_colored_keys(expanded()))
+            .unwrap();
 
         assert_eq!(expected, result);
     }
 
     #[test]
     fn it_formats_a_multidimensional_object() {
-        colored::control::set_override(true);
         let first_level_object = Object {
-            members: vec![("member", Box::new(String { value: "value" }))],
+            members: vec![(
+                "member".into(),
+                Value::from(String {
+                    value: "value".into(),
+                    ..String::default()
+                }),
+            )],
+            ..Object::default()
         };
         let object = Object {
             members: vec![
-                ("member", Box::new(String { value: "value" })),
-                ("number", Box::new(Number { value: 2.0 })),
-                ("child", Box::new(first_level_object)),
+                (
+                    "member".into(),
+                    Value::from(String {
+                        value: "value".into(),
+                        ..String::default()
+                    }),
+                ),
+                (
+                    "number".into(),
+                    Value::from(Number {
+                        lexeme: "2".into(),
+                        ..Number::default()
+                    }),
+                ),
+                ("child".into(), Value::from(first_level_object)),
             ],
+            ..Object::default()
         };
         let expected = "{\n  \u{1b}[1;95m\"member\"\u{1b}[0m: \"value\",\n  \u{1b}[1;95m\"number\"\u{1b}[0m: 2,\n  \u{1b}[1;95m\"child\"\u{1b}[0m: {\n    \u{1b}[1;95m\"member\"\u{1b}[0m: \"value\"\n  }\n}";
-        let result = object.format_as_root();
-        colored::control::unset_override();
+        let result = object
+            .format_as_root(&with_colored_keys(expanded()))
+            .unwrap();
+
+        assert_eq!(expected, result);
+    }
+
+    #[test]
+    fn it_formats_a_compact_object() {
+        let first_level_object = Object {
+            members: vec![(
+                "member".into(),
+                Value::from(String {
+                    value: "value".into(),
+                    ..String::default()
+                }),
+            )],
+            ..Object::default()
+        };
+        let object = Object {
+            members: vec![
+                (
+                    "number".into(),
+                    Value::from(Number {
+                        lexeme: "2".into(),
+                        ..Number::default()
+                    }),
+                ),
+                ("child".into(), Value::from(first_level_object)),
+            ],
+            ..Object::default()
+        };
+        let expected = "{\u{1b}[1;95m\"number\"\u{1b}[0m:2,\u{1b}[1;95m\"child\"\u{1b}[0m:{\u{1b}[1;95m\"member\"\u{1b}[0m:\"value\"}}";
+        let result = object
+            .format_compact(Compact::Tight, &with_colored_keys(FormatOptions::default()))
+            .unwrap();
+
+        assert_eq!(expected, result);
+    }
+
+    #[test]
+    fn it_keeps_small_objects_on_one_line() {
+        let object = Object {
+            members: vec![
+                (
+                    "member".into(),
+                    Value::from(String {
+                        value: "value".into(),
+                        ..String::default()
+                    }),
+                ),
+                (
+                    "number".into(),
+                    Value::from(Number {
+                        lexeme: "2".into(),
+                        ..Number::default()
+                    }),
+                ),
+            ],
+            ..Object::default()
+        };
+        let expected =
+            "{\u{1b}[1;95m\"member\"\u{1b}[0m: \"value\", \u{1b}[1;95m\"number\"\u{1b}[0m: 2}";
+        let result = object
+            .format_as_root(&with_colored_keys(FormatOptions::default()))
+            .unwrap();
+
+        assert_eq!(expected, result);
+    }
+
+    #[test]
+    fn it_sorts_nested_keys_up_to_a_depth() {
+        let child = Object {
+            members: vec![
+                (
+                    "b".into(),
+                    Value::from(Number {
+                        lexeme: "1".into(),
+                        ..Number::default()
+                    }),
+                ),
+                (
+                    "a".into(),
+                    Value::from(Number {
+                        lexeme: "2".into(),
+                        ..Number::default()
+                    }),
+                ),
+            ],
+            ..Object::default()
+        };
+        let mut object = Object {
+            members: vec![
+                ("z".into(), Value::from(child)),
+                (
+                    "y".into(),
+                    Value::from(Number {
+                        lexeme: "3".into(),
+                        ..Number::default()
+                    }),
+                ),
+            ],
+            ..Object::default()
+        };
+        let sort = KeySort {
+            ordering: Some(KeyOrdering::Lexical),
+            depth: Some(1),
+            ..KeySort::default()
+        };
+        object.sort_keys(&sort, 0);
+        let result = object
+            .format_compact(Compact::Tight, &FormatOptions::default())
+            .unwrap();
+        let expected = "{\"y\":3,\"z\":{\"b\":1,\"a\":2}}";
+
+        assert_eq!(expected, result);
+    }
+
+    #[test]
+    fn it_sorts_keys_by_their_unescaped_names() {
+        let text = r#"{"c": 1, "\u0062": 2, "a": 3, "\u0064": 4}"#;
+        let Value::Object(mut object) = crate::parse(text, Dialect::Json).unwrap() else {
+            panic!("expected an object");
+        };
+        let sort = KeySort {
+            ordering: Some(KeyOrdering::Lexical),
+            priority: vec!["d".to_string()],
+            ..KeySort::default()
+        };
+        object.sort_keys(&sort, 0);
+        let result = object
+            .format_compact(Compact::Tight, &FormatOptions::default())
+            .unwrap();
+        let expected = r#"{"\u0064":4,"a":3,"\u0062":2,"c":1}"#;
+
+        assert_eq!(expected, result);
+    }
+
+    #[test]
+    fn it_paints_every_token_with_the_theme() {
+        let object = Object {
+            members: vec![
+                (
+                    "a".into(),
+                    Value::from(Number {
+                        lexeme: "1".into(),
+                        ..Number::default()
+                    }),
+                ),
+                ("b".into(), Value::from(Null::default())),
+            ],
+            ..Object::default()
+        };
+        let options = FormatOptions {
+            theme: Theme::built_in("monochrome-bold"),
+            ..FormatOptions::default()
+        };
+        let expected = "\u{1b}[2m{\u{1b}[0m\u{1b}[1m\"a\"\u{1b}[0m\u{1b}[2m:\u{1b}[0m 1\u{1b}[2m,\u{1b}[0m \u{1b}[1m\"b\"\u{1b}[0m\u{1b}[2m:\u{1b}[0m \u{1b}[1mnull\u{1b}[0m\u{1b}[2m}\u{1b}[0m";
+        let result = object.format_as_root(&options).unwrap();
+
+        assert_eq!(expected, result);
+    }
+
+    #[test]
+    fn it_leaves_identifiers_unquoted_in_json5() {
+        let object = Object {
+            members: vec![
+                ("name".into(), Value::from(Null::default())),
+                ("$id_2".into(), Value::from(Null::default())),
+                ("2nd".into(), Value::from(Null::default())),
+                ("with space".into(), Value::from(Null::default())),
+                ("caf\\u00e9".into(), Value::from(Null::default())),
+            ],
+            ..Object::default()
+        };
+        let options = FormatOptions {
+            output: Dialect::Json5,
+            ..FormatOptions::default()
+        };
+        let expected =
+            "{name:null,$id_2:null,\"2nd\":null,\"with space\":null,\"caf\\u00e9\":null}";
+        let result = object.format_compact(Compact::Tight, &options).unwrap();
 
         assert_eq!(expected, result);
     }
+
+    #[test]
+    fn it_gets_members_by_their_unescaped_name() {
+        let number = |lexeme: &'static str| {
+            Value::from(Number {
+                lexeme: lexeme.into(),
+                ..Number::default()
+            })
+        };
+        let object = Object {
+            members: vec![
+                ("a".into(), number("1")),
+                ("caf\\u00e9".into(), number("2")),
+                ("a".into(), number("3")),
+            ],
+            ..Object::default()
+        };
+
+        assert_eq!(Some(&number("3")), object.get("a"));
+        assert_eq!(Some(&number("2")), object.get("café"));
+        assert_eq!(None, object.get("b"));
+    }
 }
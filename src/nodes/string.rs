@@ -16,20 +16,127 @@ You should have received a copy of the GNU General Public License
 along with this program.  If not, see <http://www.gnu.org/licenses/>.
 */
 
-use super::Node;
+use super::{Comments, FormatError, Node, Span, ToDoc};
+use crate::layout::Doc;
+use crate::options::{Compact, FormatOptions};
+use crate::theme::Token;
+use std::borrow::Cow;
+use std::fmt::Write;
 
+/// A string, holding its contents with JSON escapes.
+#[derive(Clone, Debug, Default, PartialEq)]
 pub struct String<'a> {
-    pub value: &'a str,
+    pub value: Cow<'a, str>,
+    pub span: Span,
+    pub comments: Option<Box<Comments<'a>>>,
+}
+
+impl<'a> ToDoc for String<'a> {
+    fn to_doc(&self, options: &FormatOptions) -> Result<Doc, FormatError> {
+        Ok(Doc::token(
+            format!("\"{}\"", self.value),
+            Token::String,
+            options,
+        ))
+    }
 }
 
 impl<'a> Node<'a> for String<'a> {
-    fn format_as_child(&self, _tabs: usize) -> std::string::String {
-        let value = self.value;
-        format!("\"{value}\"")
+    fn write_compact(
+        &self,
+        _compact: Compact,
+        options: &FormatOptions,
+        out: &mut dyn Write,
+    ) -> Result<(), FormatError> {
+        Ok(out.write_str(&options.paint(Token::String, &format!("\"{}\"", self.value)))?)
+    }
+
+    fn write_canonical(&self, out: &mut dyn Write) -> Result<(), FormatError> {
+        Ok(out.write_str(&escape_canonical(&unescape(&self.value)?))?)
     }
+}
+
+/// Decodes the escape sequences of the raw contents of a JSON string.
+pub fn unescape(raw: &str) -> Result<std::string::String, FormatError> {
+    let mut value = std::string::String::with_capacity(raw.len());
+    let mut chars = raw.char_indices();
+
+    while let Some((start, c)) = chars.next() {
+        if c != '\\' {
+            value.push(c);
+            continue;
+        }
 
-    fn format_as_root(&self) -> std::string::String {
-        self.format_as_child(0)
+        match chars.next().map(|(_, c)| c) {
+            Some('b') => value.push('\u{8}'),
+            Some('f') => value.push('\u{c}'),
+            Some('n') => value.push('\n'),
+            Some('r') => value.push('\r'),
+            Some('t') => value.push('\t'),
+            Some('u') => {
+                let mut units = vec![hex_unit(&mut chars)];
+                if (0xD800..0xDC00).contains(&units[0]) && raw[start + 6..].starts_with("\\u") {
+                    chars.nth(1);
+                    units.push(hex_unit(&mut chars));
+                }
+
+                for c in char::decode_utf16(units) {
+                    let c = c.map_err(|_| {
+                        FormatError::InvalidEscape(raw[start..start + 6].to_string())
+                    })?;
+                    value.push(c);
+                }
+            }
+            Some(c) => value.push(c),
+            None => {}
+        }
+    }
+
+    Ok(value)
+}
+
+/// The raw contents of a JSON string with their escapes decoded, or as they
+/// are written when an escape encodes no character.
+pub fn unescaped(raw: &str) -> Cow<'_, str> {
+    if !raw.contains('\\') {
+        return Cow::Borrowed(raw);
+    }
+
+    unescape(raw).map_or(Cow::Borrowed(raw), Cow::Owned)
+}
+
+fn hex_unit(chars: &mut std::str::CharIndices) -> u16 {
+    let digits: std::string::String = chars.take(4).map(|(_, c)| c).collect();
+
+    u16::from_str_radix(&digits, 16).unwrap()
+}
+
+/// Quotes a string using the minimal escaping required by RFC 8785.
+pub fn escape_canonical(value: &str) -> std::string::String {
+    let mut escaped = std::string::String::with_capacity(value.len() + 2);
+    escaped.push('"');
+
+    for c in value.chars() {
+        escape_char(c, &mut escaped);
+    }
+
+    escaped.push('"');
+    escaped
+}
+
+/// Appends a character to the contents of a JSON string, escaping it only
+/// when JSON requires it.
+pub fn escape_char(c: char, escaped: &mut std::string::String) {
+    match c {
+        '"' => escaped.push_str("\\\""),
+        '\\' => escaped.push_str("\\\\"),
+        '\u{8}' => escaped.push_str("\\b"),
+        '\u{c}' => escaped.push_str("\\f"),
+        '\n' => escaped.push_str("\\n"),
+        '\r' => escaped.push_str("\\r"),
+        '\t' => escaped.push_str("\\t"),
+        c if c < ' ' => escaped.push_str(&format!("\\u{:04x}", c as u32)),
+        c => escaped.push(c),
     }
 }
 
@@ -38,20 +145,54 @@ mod string_tests {
     use super::*;
 
     #[test]
-    fn it_formats_string_as_child() {
-        let string = String { value: "foo" };
+    fn it_formats_string_compact() {
+        let string = String {
+            value: "foo".into(),
+            ..String::default()
+        };
         let expected = "\"foo\"";
-        let result = string.format_as_child(0);
+        let result = string
+            .format_compact(Compact::Tight, &FormatOptions::default())
+            .unwrap();
 
         assert_eq!(expected, result);
     }
 
     #[test]
     fn it_formats_string_as_root() {
-        let string = String { value: "foo" };
+        let string = String {
+            value: "foo".into(),
+            ..String::default()
+        };
         let expected = "\"foo\"";
-        let result = string.format_as_root();
+        let result = string.format_as_root(&FormatOptions::default()).unwrap();
 
         assert_eq!(expected, result);
     }
+
+    #[test]
+    fn it_unescapes_strings() {
+        let raw = "a\\\"b\\\\c\\/d\\n\\u00e9\\ud83d\\ude00";
+        let expected = "a\"b\\c/d\né😀";
+
+        assert_eq!(Ok(expected.to_string()), unescape(raw));
+    }
+
+    #[test]
+    fn it_rejects_unpaired_surrogates() {
+        let expected = Err(FormatError::InvalidEscape("\\ud83d".to_string()));
+
+        assert_eq!(expected, unescape("\\ud83d"));
+    }
+
+    #[test]
+    fn it_formats_canonical_strings() {
+        let string = String {
+            value: "\\u20ac$\\u000F\\u000aA'\\u0042\\u0022\\u005c\\\\\\\"\\/".into(),
+            ..String::default()
+        };
+        let expected = "\"€$\\u000f\\nA'B\\\"\\\\\\\\\\\"/\"";
+
+        assert_eq!(Ok(expected.to_string()), string.format_canonical());
+    }
 }
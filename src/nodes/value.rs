@@ -0,0 +1,331 @@
+/*
+jfmt
+Copyright (C) 2024 - Víctor Berga
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <http://www.gnu.org/licenses/>.
+*/
+
+use super::{
+    comments_of, inline, unescaped, Array, Boolean, Comments, FormatError, Node, Null, Number,
+    Object, String, ToDoc,
+};
+use crate::layout::{self, Doc};
+use crate::options::{Compact, FormatOptions};
+use crate::sort::KeySort;
+use crate::theme::Token;
+use std::fmt::Write;
+
+/// Byte offsets of a value in the text it was parsed from. Values that were
+/// not parsed have an empty span at 0.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+}
+
+/// A parsed JSON value, the tree that rendering, queries and edits work on.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Value<'a> {
+    Object(Object<'a>),
+    Array(Array<'a>),
+    String(String<'a>),
+    Number(Number<'a>),
+    Bool(Boolean<'a>),
+    Null(Null<'a>),
+}
+
+impl Span {
+    pub fn new(start: usize, end: usize) -> Self {
+        Span { start, end }
+    }
+}
+
+impl<'a> From<pest::Span<'a>> for Span {
+    fn from(span: pest::Span<'a>) -> Self {
+        Span::new(span.start(), span.end())
+    }
+}
+
+impl<'a> Value<'a> {
+    pub fn span(&self) -> Span {
+        match self {
+            Value::Object(object) => object.span,
+            Value::Array(array) => array.span,
+            Value::String(string) => string.span,
+            Value::Number(number) => number.span,
+            Value::Bool(boolean) => boolean.span,
+            Value::Null(null) => null.span,
+        }
+    }
+
+    /// Comments kept around this value, when it was parsed from JSONC.
+    pub fn comments(&self) -> Option<&Comments<'a>> {
+        match self {
+            Value::Object(object) => object.comments.as_deref(),
+            Value::Array(array) => array.comments.as_deref(),
+            Value::String(string) => string.comments.as_deref(),
+            Value::Number(number) => number.comments.as_deref(),
+            Value::Bool(boolean) => boolean.comments.as_deref(),
+            Value::Null(null) => null.comments.as_deref(),
+        }
+    }
+
+    /// Attaches `comments` to the value, unless there are none.
+    pub fn with_comments(mut self, comments: Comments<'a>) -> Self {
+        if comments.is_empty() {
+            return self;
+        }
+
+        let slot = match &mut self {
+            Value::Object(object) => &mut object.comments,
+            Value::Array(array) => &mut array.comments,
+            Value::String(string) => &mut string.comments,
+            Value::Number(number) => &mut number.comments,
+            Value::Bool(boolean) => &mut boolean.comments,
+            Value::Null(null) => &mut null.comments,
+        };
+        *slot = Some(Box::new(comments));
+
+        self
+    }
+
+    /// Whether both values hold the same JSON: numbers are compared by their
+    /// exact value, strings and member names without their escapes, and members in
+    /// any order.
+    pub fn equals(&self, other: &Value) -> bool {
+        match (self, other) {
+            (Value::Object(a), Value::Object(b)) => {
+                a.members.len() == b.members.len()
+                    && a.members.iter().all(|(name, value)| {
+                        b.get(&unescaped(name))
+                            .is_some_and(|other| value.equals(other))
+                    })
+            }
+            (Value::Array(a), Value::Array(b)) => {
+                a.values.len() == b.values.len()
+                    && a.values.iter().zip(&b.values).all(|(a, b)| a.equals(b))
+            }
+            (Value::String(a), Value::String(b)) => unescaped(&a.value) == unescaped(&b.value),
+            (Value::Number(a), Value::Number(b)) => match a.compare(b) {
+                Some(order) => order.is_eq(),
+                None => a.lexeme == b.lexeme,
+            },
+            (Value::Bool(a), Value::Bool(b)) => a.value == b.value,
+            (Value::Null(_), Value::Null(_)) => true,
+            _ => false,
+        }
+    }
+
+    /// Writes the comments before the value on one line, each followed by a
+    /// space. Members write them before their name.
+    pub(crate) fn write_leading_comments(
+        &self,
+        options: &FormatOptions,
+        out: &mut dyn Write,
+    ) -> Result<(), FormatError> {
+        for comment in comments_of(self, options)
+            .iter()
+            .flat_map(|comments| &comments.leading)
+        {
+            out.write_str(&options.paint(Token::Comment, &inline(comment)?))?;
+            out.write_char(' ')?;
+        }
+
+        Ok(())
+    }
+
+    /// Writes the value compact with the comments after it, but not those
+    /// before it.
+    pub(crate) fn write_compact_after_leading(
+        &self,
+        compact: Compact,
+        options: &FormatOptions,
+        out: &mut dyn Write,
+    ) -> Result<(), FormatError> {
+        let comments = comments_of(self, options);
+
+        self.node().write_compact(compact, options, out)?;
+        for comment in comments
+            .iter()
+            .flat_map(|comments| comments.trailing.iter().chain(&comments.after))
+        {
+            out.write_char(' ')?;
+            out.write_str(&options.paint(Token::Comment, &inline(comment)?))?;
+        }
+
+        Ok(())
+    }
+
+    fn node(&self) -> &dyn Node<'a> {
+        match self {
+            Value::Object(object) => object,
+            Value::Array(array) => array,
+            Value::String(string) => string,
+            Value::Number(number) => number,
+            Value::Bool(boolean) => boolean,
+            Value::Null(null) => null,
+        }
+    }
+}
+
+impl<'a> ToDoc for Value<'a> {
+    fn to_doc(&self, options: &FormatOptions) -> Result<Doc, FormatError> {
+        self.node().to_doc(options)
+    }
+}
+
+impl<'a> Node<'a> for Value<'a> {
+    /// The root value has no container, so it places its comments itself.
+    fn write_as_root(
+        &self,
+        options: &FormatOptions,
+        out: &mut dyn Write,
+    ) -> Result<(), FormatError> {
+        let doc = match comments_of(self, options) {
+            Some(comments) => Doc::Concat(vec![
+                comments.leading_doc(options),
+                self.to_doc(options)?,
+                comments.trailing_doc(options),
+            ]),
+            None => self.to_doc(options)?,
+        };
+
+        Ok(layout::render(&doc, options, out)?)
+    }
+
+    /// Compact output has a single line, which line comments would end, so
+    /// they are written as block comments.
+    fn write_compact(
+        &self,
+        compact: Compact,
+        options: &FormatOptions,
+        out: &mut dyn Write,
+    ) -> Result<(), FormatError> {
+        self.write_leading_comments(options, out)?;
+        self.write_compact_after_leading(compact, options, out)
+    }
+
+    fn write_canonical(&self, out: &mut dyn Write) -> Result<(), FormatError> {
+        self.node().write_canonical(out)
+    }
+
+    fn sort_keys(&mut self, sort: &KeySort, depth: usize) {
+        match self {
+            Value::Object(object) => object.sort_keys(sort, depth),
+            Value::Array(array) => array.sort_keys(sort, depth),
+            _ => {}
+        }
+    }
+}
+
+impl<'a> From<Object<'a>> for Value<'a> {
+    fn from(object: Object<'a>) -> Self {
+        Value::Object(object)
+    }
+}
+
+impl<'a> From<Array<'a>> for Value<'a> {
+    fn from(array: Array<'a>) -> Self {
+        Value::Array(array)
+    }
+}
+
+impl<'a> From<String<'a>> for Value<'a> {
+    fn from(string: String<'a>) -> Self {
+        Value::String(string)
+    }
+}
+
+impl<'a> From<Number<'a>> for Value<'a> {
+    fn from(number: Number<'a>) -> Self {
+        Value::Number(number)
+    }
+}
+
+impl<'a> From<Boolean<'a>> for Value<'a> {
+    fn from(boolean: Boolean<'a>) -> Self {
+        Value::Bool(boolean)
+    }
+}
+
+impl<'a> From<Null<'a>> for Value<'a> {
+    fn from(null: Null<'a>) -> Self {
+        Value::Null(null)
+    }
+}
+
+#[cfg(test)]
+mod value_tests {
+    use super::*;
+    use crate::options::Dialect;
+
+    #[test]
+    fn it_keeps_comments_only_when_there_are_some() {
+        let plain = Value::from(Null::default()).with_comments(Comments::default());
+        let commented = Value::from(Null::default()).with_comments(Comments {
+            leading: vec!["// nothing"],
+            ..Comments::default()
+        });
+
+        assert_eq!(None, plain.comments());
+        assert_eq!(vec!["// nothing"], commented.comments().unwrap().leading);
+    }
+
+    #[test]
+    fn it_compares_values_as_json() {
+        let parse = |json| crate::parser::parse(json, Dialect::Json5).unwrap();
+        let cases = [
+            (
+                "{a: [1, 'x'], b: null}",
+                "{\"b\": null, \"a\": [1.0, \"\\u0078\"]}",
+                true,
+            ),
+            ("[1, 2]", "[2, 1]", false),
+            ("{a: 1}", "{a: 1, b: 1}", false),
+            ("0x10", "16", true),
+            ("12345678901234567891", "12345678901234567892", false),
+            ("true", "'true'", false),
+        ];
+
+        for (a, b, expected) in cases {
+            assert_eq!(expected, parse(a).equals(&parse(b)), "{a} {b}");
+        }
+    }
+
+    #[test]
+    fn it_places_the_comments_of_the_root() {
+        let value = Value::from(Boolean {
+            value: true,
+            ..Boolean::default()
+        })
+        .with_comments(Comments {
+            leading: vec!["// flag"],
+            trailing: vec!["/* on */"],
+            ..Comments::default()
+        });
+        let jsonc = FormatOptions {
+            output: Dialect::Jsonc,
+            ..FormatOptions::default()
+        };
+
+        assert_eq!(
+            "// flag\ntrue /* on */",
+            value.format_as_root(&jsonc).unwrap()
+        );
+        assert_eq!(
+            "true",
+            value.format_as_root(&FormatOptions::default()).unwrap()
+        );
+    }
+}
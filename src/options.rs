@@ -0,0 +1,197 @@
+/*
+jfmt
+Copyright (C) 2024 - Víctor Berga
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <http://www.gnu.org/licenses/>.
+*/
+
+use crate::sort::KeySort;
+use crate::theme::{Theme, Token};
+use std::fmt;
+
+/// Settings that control how a document is parsed and rendered.
+#[derive(Clone, Debug, PartialEq)]
+pub struct FormatOptions {
+    /// Syntax the document is parsed with.
+    pub input: Dialect,
+    /// Syntax the document is printed in.
+    pub output: Dialect,
+    pub indent: Indent,
+    pub newline: Newline,
+    pub compact: Option<Compact>,
+    /// Column limit that containers are kept within when printed on one line.
+    pub max_width: usize,
+    pub numbers: NumberFormat,
+    pub sort: Option<KeySort>,
+    /// Emit RFC 8785 canonical JSON, ignoring every other setting.
+    pub canonical: bool,
+    /// Colors for each kind of token, or `None` for plain output.
+    pub theme: Option<Theme>,
+}
+
+/// Syntaxes that documents are read and written in.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+pub enum Dialect {
+    /// Strict JSON, as in RFC 8259
+    Json,
+    /// JSON5: comments, unquoted keys, single quotes, trailing commas and more
+    Json5,
+    /// JSON with comments and trailing commas, as in VS Code settings
+    Jsonc,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Indent {
+    Spaces(usize),
+    Tabs,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+pub enum Newline {
+    Lf,
+    Crlf,
+}
+
+/// Single-line output styles.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+pub enum Compact {
+    /// No whitespace at all
+    Tight,
+    /// A space after each `,` and `:`
+    Spaced,
+}
+
+/// How numbers are spelled in the output.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+pub enum NumberFormat {
+    /// Keep numbers exactly as written in the input
+    Preserve,
+    /// Shortest representation that round-trips, as ECMAScript prints it
+    Canonical,
+    /// Plain decimal notation without exponents, keeping every digit
+    Decimal,
+    /// Always use an exponent, keeping every digit
+    Scientific,
+}
+
+impl Default for FormatOptions {
+    fn default() -> Self {
+        FormatOptions {
+            input: Dialect::Json,
+            output: Dialect::Json,
+            indent: Indent::Spaces(2),
+            newline: Newline::Lf,
+            compact: None,
+            max_width: 80,
+            numbers: NumberFormat::Preserve,
+            sort: None,
+            canonical: false,
+            theme: None,
+        }
+    }
+}
+
+impl FormatOptions {
+    /// Returns the leading whitespace for a line nested `level` times.
+    pub fn indentation(&self, level: usize) -> String {
+        match self.indent {
+            Indent::Spaces(width) => " ".repeat(level * width),
+            Indent::Tabs => "\t".repeat(level),
+        }
+    }
+
+    pub fn newline(&self) -> &'static str {
+        match self.newline {
+            Newline::Lf => "\n",
+            Newline::Crlf => "\r\n",
+        }
+    }
+
+    /// Applies the theme style for `token`, if any, to `text`.
+    pub fn paint(&self, token: Token, text: &str) -> String {
+        match &self.theme {
+            Some(theme) => theme.style(token).paint(text),
+            None => text.to_string(),
+        }
+    }
+}
+
+impl fmt::Display for Dialect {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Dialect::Json => "JSON",
+            Dialect::Json5 => "JSON5",
+            Dialect::Jsonc => "JSONC",
+        };
+
+        write!(f, "{name}")
+    }
+}
+
+impl Compact {
+    /// Separator placed between array values and object members.
+    pub fn item_separator(&self) -> &'static str {
+        match self {
+            Compact::Tight => ",",
+            Compact::Spaced => ", ",
+        }
+    }
+
+    /// Separator placed between an object key and its value.
+    pub fn key_separator(&self) -> &'static str {
+        match self {
+            Compact::Tight => ":",
+            Compact::Spaced => ": ",
+        }
+    }
+}
+
+#[cfg(test)]
+mod options_tests {
+    use super::*;
+
+    #[test]
+    fn it_indents_with_spaces() {
+        let options = FormatOptions {
+            indent: Indent::Spaces(4),
+            ..FormatOptions::default()
+        };
+
+        assert_eq!("        ", options.indentation(2));
+    }
+
+    #[test]
+    fn it_indents_with_tabs() {
+        let options = FormatOptions {
+            indent: Indent::Tabs,
+            ..FormatOptions::default()
+        };
+
+        assert_eq!("\t\t", options.indentation(2));
+    }
+
+    #[test]
+    fn it_uses_crlf_newlines() {
+        let options = FormatOptions {
+            newline: Newline::Crlf,
+            ..FormatOptions::default()
+        };
+
+        assert_eq!("\r\n", options.newline());
+    }
+}
@@ -0,0 +1,136 @@
+/*
+jfmt
+Copyright (C) 2024 - Víctor Berga
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <http://www.gnu.org/licenses/>.
+*/
+
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+/// Replaces the contents of a file without ever leaving it half written.
+///
+/// The text goes to a temporary file next to the destination, which then
+/// takes its place with a rename. An existing file keeps its permissions,
+/// and a symbolic link keeps pointing at the file, which is the one written.
+pub fn write_atomic(path: &Path, contents: &str) -> io::Result<()> {
+    write_atomic_with(path, |file| file.write_all(contents.as_bytes()))
+}
+
+/// Like [`write_atomic`], with the contents written by `write` as they are
+/// produced.
+pub fn write_atomic_with<E: From<io::Error>>(
+    path: &Path,
+    write: impl FnOnce(&mut File) -> Result<(), E>,
+) -> Result<(), E> {
+    let path = &resolve(path)?;
+    let temporary = temporary_path(path);
+    let result = write_temporary(&temporary, path, write)
+        .and_then(|()| fs::rename(&temporary, path).map_err(E::from));
+
+    if result.is_err() {
+        let _ = fs::remove_file(&temporary);
+    }
+
+    result
+}
+
+/// The file `path` names once symbolic links are followed, since renaming
+/// onto a link would replace the link itself. Missing files are left as they
+/// are.
+fn resolve(path: &Path) -> io::Result<PathBuf> {
+    match fs::canonicalize(path) {
+        Ok(resolved) => Ok(resolved),
+        Err(error) if error.kind() == io::ErrorKind::NotFound => Ok(path.to_path_buf()),
+        Err(error) => Err(error),
+    }
+}
+
+fn temporary_path(path: &Path) -> PathBuf {
+    let name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+
+    path.with_file_name(format!(".{name}.{}.tmp", std::process::id()))
+}
+
+fn write_temporary<E: From<io::Error>>(
+    temporary: &Path,
+    path: &Path,
+    write: impl FnOnce(&mut File) -> Result<(), E>,
+) -> Result<(), E> {
+    let mut file = OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(temporary)?;
+    write(&mut file)?;
+    file.sync_all()?;
+
+    match fs::metadata(path) {
+        Ok(metadata) => Ok(fs::set_permissions(temporary, metadata.permissions())?),
+        Err(error) if error.kind() == io::ErrorKind::NotFound => Ok(()),
+        Err(error) => Err(error.into()),
+    }
+}
+
+#[cfg(test)]
+mod output_tests {
+    use super::*;
+    use crate::scratch::Scratch;
+
+    #[test]
+    fn it_creates_missing_files() {
+        let scratch = Scratch::new("create");
+        write_atomic(&scratch.path("new.json"), "{}\n").unwrap();
+
+        assert_eq!("{}\n", scratch.read("new.json"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn it_keeps_the_permissions_of_the_file() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let scratch = Scratch::new("permissions");
+        let path = scratch.path("data.json");
+        scratch.write("data.json", "{ }");
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o640)).unwrap();
+
+        write_atomic(&path, "{}\n").unwrap();
+        let mode = fs::metadata(&path).unwrap().permissions().mode();
+
+        assert_eq!(0o640, mode & 0o777);
+        assert_eq!("{}\n", scratch.read("data.json"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn it_writes_the_target_of_symbolic_links() {
+        let scratch = Scratch::new("symlink");
+        let link = scratch.path("link.json");
+        scratch.write("target.json", "{ }");
+        std::os::unix::fs::symlink(scratch.path("target.json"), &link).unwrap();
+
+        write_atomic(&link, "{}\n").unwrap();
+        let is_link = fs::symlink_metadata(&link)
+            .unwrap()
+            .file_type()
+            .is_symlink();
+
+        assert!(is_link);
+        assert_eq!("{}\n", scratch.read("target.json"));
+    }
+}
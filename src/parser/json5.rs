@@ -0,0 +1,170 @@
+/*
+jfmt
+Copyright (C) 2024 - Víctor Berga
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <http://www.gnu.org/licenses/>.
+*/
+
+use crate::nodes::{escape_char, Array, Boolean, Null, Number, Object, String, Value};
+use pest::error::Error;
+use pest::iterators::Pair;
+use pest::Parser;
+use pest_derive::Parser;
+use std::borrow::Cow;
+
+/// Parses a JSON5 document into the same tree as JSON. Strings are rewritten
+/// with JSON escapes, while numbers keep their JSON5 spelling until printed.
+pub fn parse(json5: &str) -> Result<Value<'_>, Box<Error<Rule>>> {
+    let json5 = JSON5Parser::parse(Rule::json5, json5)?.next().unwrap();
+
+    Ok(parse_value(json5))
+}
+
+#[derive(Parser)]
+#[grammar = "json5.pest"]
+struct JSON5Parser;
+
+fn parse_value<'a>(pair: Pair<'a, Rule>) -> Value<'a> {
+    let span = pair.as_span().into();
+
+    match pair.as_rule() {
+        Rule::object => Value::Object(Object {
+            members: pair.into_inner().map(parse_member).collect(),
+            span,
+            comments: None,
+        }),
+        Rule::array => Value::Array(Array {
+            values: pair.into_inner().map(parse_value).collect(),
+            span,
+            comments: None,
+        }),
+        Rule::string => Value::String(String {
+            value: string_value(pair),
+            span,
+            comments: None,
+        }),
+        Rule::number => Value::Number(Number {
+            lexeme: pair.as_str().into(),
+            span,
+            comments: None,
+        }),
+        Rule::boolean => Value::Bool(Boolean {
+            value: pair.as_str() == "true",
+            span,
+            comments: None,
+        }),
+        Rule::null => Value::Null(Null {
+            span,
+            comments: None,
+        }),
+        _ => unreachable!(),
+    }
+}
+
+fn parse_member<'a>(pair: Pair<'a, Rule>) -> (Cow<'a, str>, Value<'a>) {
+    let mut inner_rules = pair.into_inner();
+    let name = inner_rules.next().unwrap();
+    // Identifiers can only hold letters, digits, `$`, `_` and `\u` escapes,
+    // which are all valid in a JSON string as they are.
+    let name = match name.as_rule() {
+        Rule::identifier => Cow::Borrowed(name.as_str()),
+        _ => string_value(name),
+    };
+
+    (name, parse_value(inner_rules.next().unwrap()))
+}
+
+fn string_value<'a>(pair: Pair<'a, Rule>) -> Cow<'a, str> {
+    to_json(pair.into_inner().next().unwrap().as_str())
+}
+
+/// Rewrites the contents of a JSON5 string, which the grammar has already
+/// validated, with the escapes of JSON.
+fn to_json(raw: &str) -> Cow<'_, str> {
+    let mut json = std::string::String::with_capacity(raw.len());
+    let mut chars = raw.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            escape_char(c, &mut json);
+            continue;
+        }
+
+        match chars.next().unwrap() {
+            c @ ('"' | '\\' | '/' | 'b' | 'f' | 'n' | 'r' | 't') => {
+                json.push('\\');
+                json.push(c);
+            }
+            'u' => {
+                json.push_str("\\u");
+                json.extend(chars.by_ref().take(4));
+            }
+            'v' => json.push_str("\\u000b"),
+            '0' => json.push_str("\\u0000"),
+            'x' => {
+                let digits: std::string::String = chars.by_ref().take(2).collect();
+                let c = char::from(u8::from_str_radix(&digits, 16).unwrap());
+                escape_char(c, &mut json);
+            }
+            // A backslash before a line terminator continues the string on
+            // the next line.
+            '\r' => {
+                chars.next_if_eq(&'\n');
+            }
+            '\n' | '\u{2028}' | '\u{2029}' => {}
+            c => escape_char(c, &mut json),
+        }
+    }
+
+    if json == raw {
+        Cow::Borrowed(raw)
+    } else {
+        Cow::Owned(json)
+    }
+}
+
+#[cfg(test)]
+mod json5_tests {
+    use super::*;
+
+    #[test]
+    fn it_rewrites_strings_with_json_escapes() {
+        let cases = [
+            ("plain \\\"text\\\"", "plain \\\"text\\\""),
+            ("it\\'s \"quoted\"", "it's \\\"quoted\\\""),
+            ("\\x41\\v\\0\\a", "A\\u000b\\u0000a"),
+            ("one \\\ntwo \\\r\nthree", "one two three"),
+            ("tab\there", "tab\\there"),
+            ("\\u00e9", "\\u00e9"),
+        ];
+
+        for (raw, expected) in cases {
+            assert_eq!(expected, to_json(raw));
+        }
+    }
+
+    #[test]
+    fn it_rejects_invalid_json5() {
+        for json5 in [
+            "[01]",
+            "{a b: 1}",
+            "'\\1'",
+            "[,]",
+            "{\"a\": 1,,}",
+            "/* open",
+        ] {
+            assert!(parse(json5).is_err(), "{json5}");
+        }
+    }
+}
@@ -0,0 +1,320 @@
+/*
+jfmt
+Copyright (C) 2024 - Víctor Berga
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <http://www.gnu.org/licenses/>.
+*/
+
+use crate::nodes::{Array, Boolean, Comments, Null, Number, Object, String, Value};
+use pest::error::Error;
+use pest::iterators::Pair;
+use pest::Parser;
+use pest_derive::Parser;
+use std::borrow::Cow;
+
+/// Parses a JSONC document, attaching every comment to the value next to it.
+pub fn parse(jsonc: &str) -> Result<Value<'_>, Box<Error<Rule>>> {
+    let mut root = Children::new(jsonc);
+
+    for pair in JSONCParser::parse(Rule::jsonc, jsonc)? {
+        match pair.as_rule() {
+            Rule::comment => root.comment(pair),
+            Rule::EOI => {}
+            _ => {
+                let end = pair.as_span().end();
+                let (value, inside) = parse_value(pair);
+                root.push(value, with_inside(inside), end);
+            }
+        }
+    }
+
+    let (mut values, _) = root.finish();
+    let (value, comments) = values.pop().unwrap();
+
+    Ok(value.with_comments(comments))
+}
+
+#[derive(Parser)]
+#[grammar = "jsonc.pest"]
+struct JSONCParser;
+
+/// Collects the children of a container, or the root value, deciding which
+/// child each comment belongs to: a comment on the line where a child ends
+/// trails it, and any other comment leads the next child.
+struct Children<'a, C> {
+    input: &'a str,
+    children: Vec<(C, Comments<'a>)>,
+    /// Comments on lines of their own, waiting for the next child.
+    pending: Vec<&'a str>,
+    /// Where the last child, or its last trailing comment, ends.
+    end: usize,
+}
+
+impl<'a, C> Children<'a, C> {
+    fn new(input: &'a str) -> Self {
+        Children {
+            input,
+            children: Vec::new(),
+            pending: Vec::new(),
+            end: 0,
+        }
+    }
+
+    fn push(&mut self, child: C, mut comments: Comments<'a>, end: usize) {
+        comments.leading.splice(0..0, self.pending.drain(..));
+        self.children.push((child, comments));
+        self.end = end;
+    }
+
+    fn comment(&mut self, pair: Pair<'a, Rule>) {
+        let span = pair.as_span();
+        let same_line =
+            self.pending.is_empty() && !self.input[self.end..span.start()].contains('\n');
+
+        match self.children.last_mut() {
+            Some((_, comments)) if same_line => {
+                comments.trailing.push(pair.as_str());
+                self.end = span.end();
+            }
+            _ => self.pending.push(pair.as_str()),
+        }
+    }
+
+    /// Returns the children, and the comments of an empty container, which
+    /// have no child to belong to.
+    fn finish(mut self) -> (Vec<(C, Comments<'a>)>, Vec<&'a str>) {
+        match self.children.last_mut() {
+            Some((_, comments)) => comments.after.append(&mut self.pending),
+            None => return (self.children, self.pending),
+        }
+
+        (self.children, Vec::new())
+    }
+}
+
+/// Comments inside an empty container stay between its brackets.
+fn with_inside(inside: Vec<&str>) -> Comments<'_> {
+    Comments {
+        inside,
+        ..Comments::default()
+    }
+}
+
+/// Builds the node for a value, along with the comments inside it when it is
+/// an empty container.
+fn parse_value<'a>(pair: Pair<'a, Rule>) -> (Value<'a>, Vec<&'a str>) {
+    let span = pair.as_span().into();
+
+    match pair.as_rule() {
+        Rule::object => {
+            let mut members = Children::new(pair.as_span().get_input());
+            for pair in pair.into_inner() {
+                match pair.as_rule() {
+                    Rule::comment => members.comment(pair),
+                    _ => {
+                        let end = pair.as_span().end();
+                        let (name, value, comments) = parse_member(pair);
+                        members.push((name, value), comments, end);
+                    }
+                }
+            }
+
+            let (members, inside) = members.finish();
+            let members = members
+                .into_iter()
+                .map(|((name, value), comments)| (name, value.with_comments(comments)))
+                .collect();
+
+            (
+                Value::Object(Object {
+                    members,
+                    span,
+                    comments: None,
+                }),
+                inside,
+            )
+        }
+        Rule::array => {
+            let mut values = Children::new(pair.as_span().get_input());
+            for pair in pair.into_inner() {
+                match pair.as_rule() {
+                    Rule::comment => values.comment(pair),
+                    _ => {
+                        let end = pair.as_span().end();
+                        let (value, inside) = parse_value(pair);
+                        values.push(value, with_inside(inside), end);
+                    }
+                }
+            }
+
+            let (values, inside) = values.finish();
+            let values = values
+                .into_iter()
+                .map(|(value, comments)| value.with_comments(comments))
+                .collect();
+
+            (
+                Value::Array(Array {
+                    values,
+                    span,
+                    comments: None,
+                }),
+                inside,
+            )
+        }
+        Rule::string => (
+            Value::String(String {
+                value: Cow::Borrowed(pair.into_inner().next().unwrap().as_str()),
+                span,
+                comments: None,
+            }),
+            Vec::new(),
+        ),
+        Rule::number => (
+            Value::Number(Number {
+                lexeme: pair.as_str().into(),
+                span,
+                comments: None,
+            }),
+            Vec::new(),
+        ),
+        Rule::boolean => (
+            Value::Bool(Boolean {
+                value: pair.as_str() == "true",
+                span,
+                comments: None,
+            }),
+            Vec::new(),
+        ),
+        Rule::null => (
+            Value::Null(Null {
+                span,
+                comments: None,
+            }),
+            Vec::new(),
+        ),
+        _ => unreachable!(),
+    }
+}
+
+/// Comments between the key and the value of a member lead the member.
+fn parse_member<'a>(pair: Pair<'a, Rule>) -> (Cow<'a, str>, Value<'a>, Comments<'a>) {
+    let mut inner_rules = pair.into_inner();
+    let name = inner_rules
+        .next()
+        .unwrap()
+        .into_inner()
+        .next()
+        .unwrap()
+        .as_str();
+    let mut comments = Comments::default();
+
+    for pair in inner_rules {
+        match pair.as_rule() {
+            Rule::comment => comments.leading.push(pair.as_str()),
+            _ => {
+                let (value, inside) = parse_value(pair);
+                comments.inside = inside;
+
+                return (Cow::Borrowed(name), value, comments);
+            }
+        }
+    }
+
+    unreachable!()
+}
+
+#[cfg(test)]
+mod jsonc_tests {
+    use super::*;
+    use crate::nodes::Node;
+    use crate::options::{Compact, Dialect, FormatOptions};
+
+    fn reformat(jsonc: &str) -> std::string::String {
+        let options = FormatOptions {
+            input: Dialect::Jsonc,
+            output: Dialect::Jsonc,
+            ..FormatOptions::default()
+        };
+
+        parse(jsonc).unwrap().format_as_root(&options).unwrap()
+    }
+
+    #[test]
+    fn it_keeps_leading_and_trailing_comments() {
+        let jsonc = "// Editor settings\n{\n  // Font\n  \"editor.fontSize\": 14, // pixels\n  \"files.exclude\": {\"**/.git\": true,},\n  /* end */\n}";
+        let expected = "// Editor settings\n{\n  // Font\n  \"editor.fontSize\": 14, // pixels\n  \"files.exclude\": {\"**/.git\": true}\n  /* end */\n}";
+
+        assert_eq!(expected, reformat(jsonc));
+    }
+
+    #[test]
+    fn it_keeps_comments_of_array_values() {
+        let jsonc = "[1, /* one */\n  // two\n  2]";
+        let expected = "[\n  1, /* one */\n  // two\n  2\n]";
+
+        assert_eq!(expected, reformat(jsonc));
+    }
+
+    #[test]
+    fn it_keeps_comments_of_empty_containers_inside_them() {
+        let jsonc =
+            "{\"a\": [ // none yet\n], \"c\": { // only a comment\n}, \"d\": [ /* inline */ ]}";
+        let expected = "{\n  \"a\": [\n    // none yet\n  ],\n  \"c\": {\n    // only a comment\n  },\n  \"d\": [ /* inline */ ]\n}";
+
+        assert_eq!(expected, reformat(jsonc));
+        assert_eq!("{ /* empty */ }", reformat("{ /* empty */ }"));
+    }
+
+    #[test]
+    fn it_writes_comments_of_compact_output_as_block_comments() {
+        let options = FormatOptions {
+            input: Dialect::Jsonc,
+            output: Dialect::Jsonc,
+            compact: Some(Compact::Tight),
+            ..FormatOptions::default()
+        };
+        let compact =
+            |jsonc| crate::format_with(jsonc, &options).map_err(|error| error.to_string());
+
+        assert_eq!(
+            Ok("/* Font */ {\"size\":14 /* pixels */,\"e\":[ /* none */ ]} /* end */".to_string()),
+            compact("// Font\n{\"size\": 14, // pixels\n\"e\": [ //none\n]} /* end */")
+        );
+        assert_eq!(
+            Ok("{/* lead a */ \"a\":1,/* lead b */ \"b\":[/* one */ 1]}".to_string()),
+            compact(
+                "{\n  // lead a\n  \"a\": 1,\n  /* lead b */ \"b\": [\n    // one\n    1\n  ]\n}"
+            )
+        );
+        assert_eq!(
+            Err("comment // a */ b cannot be written on a single line".to_string()),
+            compact("[1 // a */ b\n]")
+        );
+    }
+
+    #[test]
+    fn it_drops_comments_from_json_output() {
+        let jsonc = "{\n  // Font\n  \"size\": 14, // pixels\n}";
+        let options = FormatOptions {
+            input: Dialect::Jsonc,
+            ..FormatOptions::default()
+        };
+
+        assert_eq!(
+            "{\"size\": 14}",
+            parse(jsonc).unwrap().format_as_root(&options).unwrap()
+        );
+    }
+}
@@ -16,6 +16,178 @@ You should have received a copy of the GNU General Public License
 along with this program.  If not, see <http://www.gnu.org/licenses/>.
 */
 
+mod json5;
+mod jsonc;
 mod parse;
 
-pub use parse::parse;
+use crate::nodes::Value;
+use crate::options::Dialect;
+use pest::error::{Error, LineColLocation};
+use std::fmt;
+
+/// A document that does not follow the syntax it was parsed with.
+#[derive(Debug)]
+pub struct ParseError(Syntax);
+
+/// The error of the parser of each dialect, whose rules are its own.
+#[derive(Debug)]
+enum Syntax {
+    Json(Box<Error<parse::Rule>>),
+    Json5(Box<Error<json5::Rule>>),
+    Jsonc(Box<Error<jsonc::Rule>>),
+}
+
+impl ParseError {
+    /// What is wrong at the location of the error, such as the tokens that
+    /// were expected there.
+    pub fn message(&self) -> String {
+        match &self.0 {
+            Syntax::Json(error) => error.variant.message().into_owned(),
+            Syntax::Json5(error) => error.variant.message().into_owned(),
+            Syntax::Jsonc(error) => error.variant.message().into_owned(),
+        }
+    }
+
+    /// The line the error starts on, counting from 1.
+    pub fn line(&self) -> usize {
+        self.position().0
+    }
+
+    /// The column the error starts at, counting characters from 1.
+    pub fn column(&self) -> usize {
+        self.position().1
+    }
+
+    fn position(&self) -> (usize, usize) {
+        let line_col = match &self.0 {
+            Syntax::Json(error) => &error.line_col,
+            Syntax::Json5(error) => &error.line_col,
+            Syntax::Jsonc(error) => &error.line_col,
+        };
+
+        match *line_col {
+            LineColLocation::Pos(position) | LineColLocation::Span(position, _) => position,
+        }
+    }
+
+    /// Names the input in the location of the error.
+    pub fn with_path(self, path: &str) -> Self {
+        ParseError(match self.0 {
+            Syntax::Json(error) => Syntax::Json(Box::new(error.with_path(path))),
+            Syntax::Json5(error) => Syntax::Json5(Box::new(error.with_path(path))),
+            Syntax::Jsonc(error) => Syntax::Jsonc(Box::new(error.with_path(path))),
+        })
+    }
+
+    /// Places the error in a larger input whose line `first_line` is where
+    /// the parsed text begins.
+    pub fn on_line(self, first_line: usize) -> Self {
+        ParseError(match self.0 {
+            Syntax::Json(error) => Syntax::Json(renumber(error, first_line)),
+            Syntax::Json5(error) => Syntax::Json5(renumber(error, first_line)),
+            Syntax::Jsonc(error) => Syntax::Jsonc(renumber(error, first_line)),
+        })
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.0 {
+            Syntax::Json(error) => write!(f, "invalid {}\n{error}", Dialect::Json),
+            Syntax::Json5(error) => write!(f, "invalid {}\n{error}", Dialect::Json5),
+            Syntax::Jsonc(error) => write!(f, "invalid {}\n{error}", Dialect::Jsonc),
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+/// Numbers the lines of an error as if the input started at `first_line`.
+fn renumber<R>(mut error: Box<Error<R>>, first_line: usize) -> Box<Error<R>> {
+    let shift = |(line, column): (usize, usize)| (line + first_line - 1, column);
+    error.line_col = match error.line_col {
+        LineColLocation::Pos(position) => LineColLocation::Pos(shift(position)),
+        LineColLocation::Span(start, end) => LineColLocation::Span(shift(start), shift(end)),
+    };
+
+    error
+}
+
+/// Values of an input that holds many, in input order.
+pub type Values<'a> = Vec<Value<'a>>;
+
+/// Parses JSON values written one after the other. An error comes with the
+/// index of the value it was found in.
+pub fn parse_stream(text: &str) -> Result<Values<'_>, (usize, ParseError)> {
+    parse::parse_stream(text).map_err(|(index, error)| (index, ParseError(Syntax::Json(error))))
+}
+
+/// Parses a document written in `dialect`.
+pub fn parse(text: &str, dialect: Dialect) -> Result<Value<'_>, ParseError> {
+    match dialect {
+        Dialect::Json => parse::parse(text).map_err(|error| ParseError(Syntax::Json(error))),
+        Dialect::Json5 => json5::parse(text).map_err(|error| ParseError(Syntax::Json5(error))),
+        Dialect::Jsonc => jsonc::parse(text).map_err(|error| ParseError(Syntax::Jsonc(error))),
+    }
+}
+
+#[cfg(test)]
+mod parser_tests {
+    use super::*;
+    use crate::nodes::Span;
+
+    /// Spans of the root object, its first member and the values in it.
+    fn spans(value: &Value) -> Vec<Span> {
+        let Value::Object(object) = value else {
+            panic!("expected an object");
+        };
+        let Value::Array(array) = &object.members[0].1 else {
+            panic!("expected an array");
+        };
+
+        let mut spans = vec![value.span(), array.span];
+        spans.extend(array.values.iter().map(Value::span));
+
+        spans
+    }
+
+    #[test]
+    fn it_records_the_span_of_every_value() {
+        let cases = [
+            (
+                Dialect::Json,
+                "{\"a\": [1, null]}",
+                [(0, 16), (6, 15), (7, 8), (10, 14)],
+            ),
+            (
+                Dialect::Json5,
+                "{a: [1, null,],}",
+                [(0, 16), (4, 14), (5, 6), (8, 12)],
+            ),
+            (
+                Dialect::Jsonc,
+                "{\"a\": [1, null]}//",
+                [(0, 16), (6, 15), (7, 8), (10, 14)],
+            ),
+        ];
+
+        for (dialect, text, expected) in cases {
+            let expected: Vec<Span> = expected.map(|(start, end)| Span::new(start, end)).into();
+
+            assert_eq!(expected, spans(&parse(text, dialect).unwrap()));
+        }
+    }
+
+    #[test]
+    fn it_locates_syntax_errors() {
+        let error = parse("{\n  \"a\": 1,\n  \"b\" 2\n}", Dialect::Json).unwrap_err();
+
+        assert_eq!((3, 3), (error.line(), error.column()));
+        assert!(
+            error.message().starts_with("expected"),
+            "{}",
+            error.message()
+        );
+        assert_eq!(5, error.on_line(3).line());
+    }
+}
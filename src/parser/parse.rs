@@ -16,36 +16,73 @@ You should have received a copy of the GNU General Public License
 along with this program.  If not, see <http://www.gnu.org/licenses/>.
 */
 
-use crate::nodes::{Array, Boolean, Node, Null, Number, Object, String};
-use pest::error::Error;
+use crate::nodes::{Array, Boolean, Null, Number, Object, String, Value};
+use crate::parser::Values;
+use pest::error::{Error, InputLocation};
 use pest::iterators::Pair;
 use pest::Parser;
 use pest_derive::Parser;
 
-pub fn parse(json: &str) -> Result<Box<dyn Node<'_> + '_>, Box<Error<Rule>>> {
+pub fn parse(json: &str) -> Result<Value<'_>, Box<Error<Rule>>> {
     let json = JSONParser::parse(Rule::json, json)?.next().unwrap();
 
     Ok(parse_value(json))
 }
 
+/// Parses values that follow each other, separated by whitespace or nothing
+/// at all. An error comes with the index of the value it was found in.
+pub fn parse_stream(json: &str) -> Result<Values<'_>, (usize, Box<Error<Rule>>)> {
+    match JSONParser::parse(Rule::json_stream, json) {
+        Ok(pairs) => Ok(pairs
+            .filter(|pair| pair.as_rule() != Rule::EOI)
+            .map(parse_value)
+            .collect()),
+        Err(error) => {
+            let offset = match error.location {
+                InputLocation::Pos(offset) => offset,
+                InputLocation::Span((start, _)) => start,
+            };
+
+            Err((values_before(json, offset), Box::new(error)))
+        }
+    }
+}
+
+/// Counts the values that end before `offset`.
+fn values_before(json: &str, offset: usize) -> usize {
+    let mut count = 0;
+    let mut start = 0;
+
+    while let Ok(mut pairs) = JSONParser::parse(Rule::first_value, &json[start..]) {
+        start += pairs.next().unwrap().as_span().end();
+        if start > offset {
+            break;
+        }
+        count += 1;
+    }
+
+    count
+}
+
 #[derive(Parser)]
 #[grammar = "json.pest"]
 struct JSONParser;
 
-pub fn parse_value<'a>(pair: Pair<'a, Rule>) -> Box<dyn Node<'a> + 'a> {
+pub fn parse_value<'a>(pair: Pair<'a, Rule>) -> Value<'a> {
     match pair.as_rule() {
-        Rule::object => Box::new(Object::new(pair)),
-        Rule::array => Box::new(Array::new(pair)),
-        Rule::string => Box::new(String::new(pair)),
-        Rule::number => Box::new(Number::new(pair)),
-        Rule::boolean => Box::new(Boolean::new(pair)),
-        Rule::null => Box::new(Null::new()),
+        Rule::object => Object::new(pair).into(),
+        Rule::array => Array::new(pair).into(),
+        Rule::string => String::new(pair).into(),
+        Rule::number => Number::new(pair).into(),
+        Rule::boolean => Boolean::new(pair).into(),
+        Rule::null => Null::new(pair).into(),
         _ => unreachable!(),
     }
 }
 
 impl<'a> Object<'a> {
     fn new(pair: Pair<'a, Rule>) -> Self {
+        let span = pair.as_span().into();
         let attributes = pair
             .into_inner()
             .map(|pair| {
@@ -58,50 +95,73 @@ impl<'a> Object<'a> {
                     .unwrap()
                     .as_str();
                 let value = parse_value(inner_rules.next().unwrap());
-                (name, value)
+                (name.into(), value)
             })
             .collect();
 
         Object {
             members: attributes,
+            span,
+            comments: None,
         }
     }
 }
 
 impl<'a> Array<'a> {
     fn new(pair: Pair<'a, Rule>) -> Self {
+        let span = pair.as_span().into();
         let values = pair.into_inner().map(|pair| parse_value(pair)).collect();
 
-        Array { values }
+        Array {
+            values,
+            span,
+            comments: None,
+        }
     }
 }
 
 impl<'a> String<'a> {
     fn new(pair: Pair<'a, Rule>) -> Self {
+        let span = pair.as_span().into();
         let value = pair.into_inner().next().unwrap().as_str();
 
-        String { value }
+        String {
+            value: value.into(),
+            span,
+            comments: None,
+        }
     }
 }
 
-impl<'a> Number {
+impl<'a> Number<'a> {
     fn new(pair: Pair<'a, Rule>) -> Self {
-        let value = pair.as_str().parse().unwrap();
+        let lexeme = pair.as_str().into();
 
-        Number { value }
+        Number {
+            lexeme,
+            span: pair.as_span().into(),
+            comments: None,
+        }
     }
 }
 
-impl<'a> Boolean {
+impl<'a> Boolean<'a> {
     fn new(pair: Pair<'a, Rule>) -> Self {
         let value = pair.as_str().parse().unwrap();
 
-        Boolean { value }
+        Boolean {
+            value,
+            span: pair.as_span().into(),
+            comments: None,
+        }
     }
 }
 
-impl Null {
-    fn new() -> Self {
-        Null {}
+impl<'a> Null<'a> {
+    fn new(pair: Pair<'a, Rule>) -> Self {
+        Null {
+            span: pair.as_span().into(),
+            comments: None,
+        }
     }
 }
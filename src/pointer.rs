@@ -0,0 +1,298 @@
+/*
+jfmt
+Copyright (C) 2024 - Víctor Berga
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <http://www.gnu.org/licenses/>.
+*/
+
+//! JSON Pointers (RFC 6901), which address one value of a document, such as
+//! `/spec/containers/0/image`.
+
+use crate::nodes::Value;
+use std::fmt;
+
+/// A parsed JSON Pointer: the member names and array indexes leading from
+/// the root to a value, without their `~0` and `~1` escapes.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Pointer {
+    segments: Vec<String>,
+}
+
+/// Reasons a pointer cannot be parsed or does not address a value.
+#[derive(Debug, PartialEq)]
+pub enum PointerError {
+    /// A pointer that is not empty has to start with `/`.
+    MissingSlash(String),
+    /// `~` is only allowed in `~0` and `~1`.
+    InvalidEscape(String),
+    /// The segment at `index` names nothing in the value it applies to.
+    Unresolved {
+        pointer: Pointer,
+        index: usize,
+        reason: Unresolved,
+    },
+}
+
+/// Why a segment names nothing.
+#[derive(Debug, PartialEq)]
+pub enum Unresolved {
+    /// The object has no member with that name.
+    NoMember,
+    /// The array has fewer elements, given here.
+    OutOfRange(usize),
+    /// The segment is not a number, or has leading zeros, but applies to an
+    /// array.
+    NotAnIndex,
+    /// The segment applies to a string, number, boolean or null, named here.
+    Scalar(&'static str),
+}
+
+impl Pointer {
+    pub fn parse(pointer: &str) -> Result<Pointer, PointerError> {
+        if pointer.is_empty() {
+            return Ok(Pointer::root());
+        }
+        let Some(pointer) = pointer.strip_prefix('/') else {
+            return Err(PointerError::MissingSlash(pointer.to_string()));
+        };
+
+        let segments = pointer.split('/').map(unescape).collect::<Result<_, _>>()?;
+
+        Ok(Pointer { segments })
+    }
+
+    /// The empty pointer, which addresses the whole document.
+    pub fn root() -> Pointer {
+        Pointer {
+            segments: Vec::new(),
+        }
+    }
+
+    /// The pointer to the member or element `segment` of the value this one
+    /// addresses.
+    pub fn child(&self, segment: &str) -> Pointer {
+        let mut segments = self.segments.clone();
+        segments.push(segment.to_string());
+
+        Pointer { segments }
+    }
+
+    pub fn is_root(&self) -> bool {
+        self.segments.is_empty()
+    }
+
+    /// Finds the value the pointer addresses in `root`, or tells which
+    /// segment is the first one that names nothing.
+    pub fn resolve<'v, 'a>(&self, root: &'v Value<'a>) -> Result<&'v Value<'a>, PointerError> {
+        let mut value = root;
+
+        for (index, segment) in self.segments.iter().enumerate() {
+            let unresolved = |reason| PointerError::Unresolved {
+                pointer: self.clone(),
+                index,
+                reason,
+            };
+
+            value = match value {
+                Value::Object(object) => object
+                    .get(segment)
+                    .ok_or_else(|| unresolved(Unresolved::NoMember))?,
+                Value::Array(array) => {
+                    let position =
+                        array_index(segment).ok_or_else(|| unresolved(Unresolved::NotAnIndex))?;
+                    array
+                        .values
+                        .get(position)
+                        .ok_or_else(|| unresolved(Unresolved::OutOfRange(array.values.len())))?
+                }
+                Value::String(_) => return Err(unresolved(Unresolved::Scalar("string"))),
+                Value::Number(_) => return Err(unresolved(Unresolved::Scalar("number"))),
+                Value::Bool(_) => return Err(unresolved(Unresolved::Scalar("boolean"))),
+                Value::Null(_) => return Err(unresolved(Unresolved::Scalar("null"))),
+            };
+        }
+
+        Ok(value)
+    }
+
+    /// The pointer up to, and without, the segment at `index`.
+    fn prefix(&self, index: usize) -> Pointer {
+        Pointer {
+            segments: self.segments[..index].to_vec(),
+        }
+    }
+}
+
+/// Decodes `~1` into `/` and `~0` into `~`, in this order, as RFC 6901
+/// requires.
+fn unescape(segment: &str) -> Result<String, PointerError> {
+    let mut unescaped = String::with_capacity(segment.len());
+    let mut chars = segment.chars();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '~' => match chars.next() {
+                Some('0') => unescaped.push('~'),
+                Some('1') => unescaped.push('/'),
+                _ => return Err(PointerError::InvalidEscape(segment.to_string())),
+            },
+            c => unescaped.push(c),
+        }
+    }
+
+    Ok(unescaped)
+}
+
+/// Array indexes are written in decimal without leading zeros. `-`, the
+/// element after the last one, never exists in a document being read.
+fn array_index(segment: &str) -> Option<usize> {
+    let digits = segment.bytes().all(|byte| byte.is_ascii_digit());
+    if segment.is_empty() || !digits || (segment.len() > 1 && segment.starts_with('0')) {
+        return None;
+    }
+
+    segment.parse().ok()
+}
+
+impl fmt::Display for Pointer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for segment in &self.segments {
+            write!(f, "/{}", segment.replace('~', "~0").replace('/', "~1"))?;
+        }
+
+        Ok(())
+    }
+}
+
+impl fmt::Display for PointerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PointerError::MissingSlash(pointer) => {
+                write!(f, "pointer {pointer:?} does not start with /")
+            }
+            PointerError::InvalidEscape(segment) => {
+                write!(f, "segment {segment:?} has a ~ not followed by 0 or 1")
+            }
+            PointerError::Unresolved {
+                pointer,
+                index,
+                reason,
+            } => {
+                let segment = &pointer.segments[*index];
+                let parent = match index {
+                    0 => "the root".to_string(),
+                    _ => pointer.prefix(*index).to_string(),
+                };
+
+                write!(f, "pointer {pointer} does not resolve: ")?;
+                match reason {
+                    Unresolved::NoMember => write!(f, "{parent} has no member {segment:?}"),
+                    Unresolved::OutOfRange(length) => {
+                        write!(f, "{parent} has no index {segment}, its length is {length}")
+                    }
+                    Unresolved::NotAnIndex => {
+                        write!(f, "{parent} is an array, and {segment:?} is not an index")
+                    }
+                    Unresolved::Scalar(kind) => {
+                        write!(f, "{parent} is a {kind}, with no {segment:?} in it")
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl std::error::Error for PointerError {}
+
+#[cfg(test)]
+mod pointer_tests {
+    use super::*;
+    use crate::options::Dialect;
+    use crate::parser::parse;
+
+    const DOCUMENT: &str = r#"{
+        "spec": {"containers": [{"image": "nginx"}, {"image": "redis"}]},
+        "a/b": 1, "m~n": 2, "": 3, " ": 4
+    }"#;
+
+    fn resolve(pointer: &str) -> Result<&'static str, PointerError> {
+        let value = parse(DOCUMENT, Dialect::Json).unwrap();
+        let span = Pointer::parse(pointer)?.resolve(&value)?.span();
+
+        Ok(&DOCUMENT[span.start..span.end])
+    }
+
+    #[test]
+    fn it_resolves_pointers_as_in_rfc_6901() {
+        let cases = [
+            ("/spec/containers/1/image", "\"redis\""),
+            ("/spec/containers/0", "{\"image\": \"nginx\"}"),
+            ("/a~1b", "1"),
+            ("/m~0n", "2"),
+            ("/", "3"),
+            ("/ ", "4"),
+        ];
+
+        for (pointer, expected) in cases {
+            assert_eq!(Ok(expected), resolve(pointer), "{pointer}");
+        }
+        assert_eq!(Ok(DOCUMENT), resolve(""));
+    }
+
+    #[test]
+    fn it_rejects_malformed_pointers() {
+        assert_eq!(
+            Err(PointerError::MissingSlash("spec".to_string())),
+            Pointer::parse("spec")
+        );
+        assert_eq!(
+            Err(PointerError::InvalidEscape("a~2".to_string())),
+            Pointer::parse("/a~2")
+        );
+    }
+
+    #[test]
+    fn it_names_the_first_segment_that_does_not_resolve() {
+        let cases = [
+            (
+                "/spec/pods/0",
+                "pointer /spec/pods/0 does not resolve: /spec has no member \"pods\"",
+            ),
+            (
+                "/spec/containers/2/image",
+                "pointer /spec/containers/2/image does not resolve: /spec/containers has no index 2, its length is 2",
+            ),
+            (
+                "/spec/containers/01",
+                "pointer /spec/containers/01 does not resolve: /spec/containers is an array, and \"01\" is not an index",
+            ),
+            (
+                "/spec/containers/-",
+                "pointer /spec/containers/- does not resolve: /spec/containers is an array, and \"-\" is not an index",
+            ),
+            (
+                "/a~1b/c",
+                "pointer /a~1b/c does not resolve: /a~1b is a number, with no \"c\" in it",
+            ),
+            (
+                "/status",
+                "pointer /status does not resolve: the root has no member \"status\"",
+            ),
+        ];
+
+        for (pointer, expected) in cases {
+            assert_eq!(expected, resolve(pointer).unwrap_err().to_string());
+        }
+    }
+}
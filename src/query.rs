@@ -0,0 +1,657 @@
+/*
+jfmt
+Copyright (C) 2024 - Víctor Berga
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <http://www.gnu.org/licenses/>.
+*/
+
+//! JSONPath queries (RFC 9535), which select any number of values of a
+//! document, such as `$.items[?@.status == "failed"].id`.
+
+use crate::nodes::{unescape, unescaped, Number, Value};
+use pest::error::{Error, ErrorVariant};
+use pest::iterators::Pair;
+use pest::Parser;
+use pest_derive::Parser;
+use std::borrow::Cow;
+use std::cmp::Ordering;
+use std::fmt;
+
+/// A parsed JSONPath query.
+#[derive(Clone, Debug)]
+pub struct Query {
+    segments: Vec<Segment>,
+}
+
+/// A query that is not valid JSONPath.
+#[derive(Debug)]
+pub struct QueryError(Box<Error<Rule>>);
+
+#[derive(Parser)]
+#[grammar = "jsonpath.pest"]
+struct JSONPathParser;
+
+#[derive(Clone, Debug)]
+enum Segment {
+    /// Selects from the children of each value.
+    Child(Vec<Selector>),
+    /// Selects from each value and all of its descendants.
+    Descendant(Vec<Selector>),
+}
+
+#[derive(Clone, Debug)]
+enum Selector {
+    Name(String),
+    Wildcard,
+    Index(i64),
+    Slice {
+        start: Option<i64>,
+        end: Option<i64>,
+        step: Option<i64>,
+    },
+    Filter(Expression),
+}
+
+#[derive(Clone, Debug)]
+enum Expression {
+    Or(Vec<Expression>),
+    And(Vec<Expression>),
+    Not(Box<Expression>),
+    /// True when the query selects anything.
+    Exists(Path),
+    Compare(Comparable, Comparison, Comparable),
+}
+
+/// A query inside a filter, which starts at the value being filtered (`@`)
+/// or at the root of the document (`$`).
+#[derive(Clone, Debug)]
+struct Path {
+    relative: bool,
+    segments: Vec<Segment>,
+}
+
+#[derive(Clone, Debug)]
+enum Comparable {
+    Number(Number<'static>),
+    String(String),
+    Bool(bool),
+    Null,
+    /// A query selecting at most one value.
+    Path(Path),
+}
+
+#[derive(Clone, Copy, Debug)]
+enum Comparison {
+    Equal,
+    NotEqual,
+    Less,
+    LessOrEqual,
+    Greater,
+    GreaterOrEqual,
+}
+
+/// One side of a comparison once it is evaluated.
+enum Operand<'o, 'a> {
+    /// A query that selects nothing.
+    Nothing,
+    Number(&'o Number<'a>),
+    String(Cow<'o, str>),
+    Bool(bool),
+    Null,
+    Container(&'o Value<'a>),
+}
+
+/// Integers in queries have to be exact in an IEEE 754 double.
+const MAX_INTEGER: i64 = (1 << 53) - 1;
+
+impl Query {
+    pub fn parse(query: &str) -> Result<Query, QueryError> {
+        let root = JSONPathParser::parse(Rule::jsonpath, query)
+            .map_err(|error| QueryError(Box::new(error)))?
+            .next()
+            .unwrap();
+
+        Ok(Query {
+            segments: segments(root)?,
+        })
+    }
+
+    /// The values the query selects in `root`, in the order RFC 9535 gives
+    /// them, which is document order for each segment.
+    pub fn select<'v, 'a>(&self, root: &'v Value<'a>) -> Vec<&'v Value<'a>> {
+        apply(&self.segments, root, root)
+    }
+}
+
+impl fmt::Display for QueryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid JSONPath query\n{}", self.0)
+    }
+}
+
+impl std::error::Error for QueryError {}
+
+fn invalid(pair: &Pair<Rule>, message: String) -> QueryError {
+    QueryError(Box::new(Error::new_from_span(
+        ErrorVariant::CustomError { message },
+        pair.as_span(),
+    )))
+}
+
+fn unsupported(function: &Pair<Rule>) -> QueryError {
+    let name = function.clone().into_inner().next().unwrap();
+
+    invalid(
+        function,
+        format!(
+            "function extensions such as {}() are not supported",
+            name.as_str()
+        ),
+    )
+}
+
+fn segments(query: Pair<Rule>) -> Result<Vec<Segment>, QueryError> {
+    query
+        .into_inner()
+        .map(|segment| {
+            let rule = segment.as_rule();
+            let selectors = selectors(segment)?;
+
+            Ok(match rule {
+                Rule::descendant_segment => Segment::Descendant(selectors),
+                _ => Segment::Child(selectors),
+            })
+        })
+        .collect()
+}
+
+fn selectors(segment: Pair<Rule>) -> Result<Vec<Selector>, QueryError> {
+    let selection = segment.into_inner().next().unwrap();
+
+    match selection.as_rule() {
+        Rule::bracketed_selection => selection.into_inner().map(selector).collect(),
+        _ => Ok(vec![selector(selection)?]),
+    }
+}
+
+fn selector(pair: Pair<Rule>) -> Result<Selector, QueryError> {
+    Ok(match pair.as_rule() {
+        Rule::name_selector => Selector::Name(string(pair.into_inner().next().unwrap())?),
+        Rule::member_name => Selector::Name(pair.as_str().to_string()),
+        Rule::wildcard => Selector::Wildcard,
+        Rule::index_selector => Selector::Index(integer(&pair)?),
+        Rule::slice_selector => {
+            let (mut start, mut end, mut step) = (None, None, None);
+            for bound in pair.into_inner() {
+                let value = Some(integer(&bound)?);
+                match bound.as_rule() {
+                    Rule::slice_start => start = value,
+                    Rule::slice_end => end = value,
+                    _ => step = value,
+                }
+            }
+
+            Selector::Slice { start, end, step }
+        }
+        _ => Selector::Filter(expression(pair.into_inner().next().unwrap())?),
+    })
+}
+
+fn expression(pair: Pair<Rule>) -> Result<Expression, QueryError> {
+    let rule = pair.as_rule();
+    let mut inner = pair.into_inner();
+
+    Ok(match rule {
+        Rule::logical_or | Rule::logical_and => {
+            let mut operands = inner.map(expression).collect::<Result<Vec<_>, _>>()?;
+            match (operands.len(), rule) {
+                (1, _) => operands.pop().unwrap(),
+                (_, Rule::logical_or) => Expression::Or(operands),
+                _ => Expression::And(operands),
+            }
+        }
+        Rule::paren_expr | Rule::test_expr => {
+            let first = inner.next().unwrap();
+            let (negated, operand) = match first.as_rule() {
+                Rule::not => (true, inner.next().unwrap()),
+                _ => (false, first),
+            };
+            let operand = match rule {
+                Rule::paren_expr => expression(operand)?,
+                _ if operand.as_rule() == Rule::function_expr => return Err(unsupported(&operand)),
+                _ => Expression::Exists(path(operand)?),
+            };
+
+            match negated {
+                true => Expression::Not(Box::new(operand)),
+                false => operand,
+            }
+        }
+        _ => {
+            let left = comparable(inner.next().unwrap())?;
+            let comparison = match inner.next().unwrap().as_str() {
+                "==" => Comparison::Equal,
+                "!=" => Comparison::NotEqual,
+                "<" => Comparison::Less,
+                "<=" => Comparison::LessOrEqual,
+                ">" => Comparison::Greater,
+                _ => Comparison::GreaterOrEqual,
+            };
+            let right = comparable(inner.next().unwrap())?;
+
+            Expression::Compare(left, comparison, right)
+        }
+    })
+}
+
+fn path(pair: Pair<Rule>) -> Result<Path, QueryError> {
+    Ok(Path {
+        relative: pair.as_rule() == Rule::current_query,
+        segments: segments(pair)?,
+    })
+}
+
+fn comparable(pair: Pair<Rule>) -> Result<Comparable, QueryError> {
+    Ok(match pair.as_rule() {
+        Rule::number => Comparable::Number(Number {
+            lexeme: Cow::Owned(pair.as_str().to_string()),
+            ..Number::default()
+        }),
+        Rule::string => Comparable::String(string(pair)?),
+        Rule::boolean => Comparable::Bool(pair.as_str() == "true"),
+        Rule::null => Comparable::Null,
+        Rule::function_expr => return Err(unsupported(&pair)),
+        _ => {
+            let query = pair.clone();
+            let path = path(pair)?;
+            let singular = path.segments.iter().all(|segment| {
+                matches!(segment, Segment::Child(selectors)
+                    if matches!(selectors[..], [Selector::Name(_) | Selector::Index(_)]))
+            });
+            if !singular {
+                return Err(invalid(
+                    &query,
+                    "a query compared in a filter can only select one value".to_string(),
+                ));
+            }
+
+            Comparable::Path(path)
+        }
+    })
+}
+
+/// The contents of a string literal, which may be single-quoted.
+fn string(pair: Pair<Rule>) -> Result<String, QueryError> {
+    let contents = pair.into_inner().next().unwrap();
+
+    unescape(contents.as_str()).map_err(|error| invalid(&contents, error.to_string()))
+}
+
+fn integer(pair: &Pair<Rule>) -> Result<i64, QueryError> {
+    pair.as_str()
+        .parse()
+        .ok()
+        .filter(|value: &i64| value.unsigned_abs() <= MAX_INTEGER as u64)
+        .ok_or_else(|| invalid(pair, format!("{} is out of range", pair.as_str())))
+}
+
+fn apply<'v, 'a>(
+    segments: &[Segment],
+    root: &'v Value<'a>,
+    current: &'v Value<'a>,
+) -> Vec<&'v Value<'a>> {
+    let mut values = vec![current];
+
+    for segment in segments {
+        let mut selected = Vec::new();
+        for value in values {
+            match segment {
+                Segment::Child(selectors) => {
+                    select(selectors, value, root, &mut selected);
+                }
+                Segment::Descendant(selectors) => {
+                    descend(value, &mut |value| {
+                        select(selectors, value, root, &mut selected)
+                    });
+                }
+            }
+        }
+        values = selected;
+    }
+
+    values
+}
+
+/// Visits `value` and then each of its descendants, in document order.
+fn descend<'v, 'a>(value: &'v Value<'a>, visit: &mut impl FnMut(&'v Value<'a>)) {
+    visit(value);
+    for child in children(value) {
+        descend(child, visit);
+    }
+}
+
+fn children<'v, 'a>(value: &'v Value<'a>) -> Vec<&'v Value<'a>> {
+    match value {
+        Value::Object(object) => object.members.iter().map(|(_, value)| value).collect(),
+        Value::Array(array) => array.values.iter().collect(),
+        _ => Vec::new(),
+    }
+}
+
+fn select<'v, 'a>(
+    selectors: &[Selector],
+    value: &'v Value<'a>,
+    root: &'v Value<'a>,
+    selected: &mut Vec<&'v Value<'a>>,
+) {
+    for selector in selectors {
+        match (selector, value) {
+            (Selector::Name(name), Value::Object(object)) => selected.extend(object.get(name)),
+            (Selector::Wildcard, _) => selected.extend(children(value)),
+            (Selector::Index(index), Value::Array(array)) => {
+                let length = array.values.len() as i64;
+                let index = if *index < 0 { length + index } else { *index };
+                if (0..length).contains(&index) {
+                    selected.push(&array.values[index as usize]);
+                }
+            }
+            (Selector::Slice { start, end, step }, Value::Array(array)) => {
+                let indexes = slice(array.values.len() as i64, *start, *end, step.unwrap_or(1));
+                selected.extend(indexes.map(|index| &array.values[index as usize]));
+            }
+            (Selector::Filter(expression), _) => selected.extend(
+                children(value)
+                    .into_iter()
+                    .filter(|child| test(expression, child, root)),
+            ),
+            _ => {}
+        }
+    }
+}
+
+/// Indexes of an array of `length` values that a slice selects, as described
+/// in section 2.3.4.2 of RFC 9535.
+fn slice(
+    length: i64,
+    start: Option<i64>,
+    end: Option<i64>,
+    step: i64,
+) -> impl Iterator<Item = i64> {
+    let normalize = |index: i64| if index < 0 { length + index } else { index };
+    let (lower, upper) = match step {
+        0 => (0, 0),
+        1.. => (
+            normalize(start.unwrap_or(0)).clamp(0, length),
+            normalize(end.unwrap_or(length)).clamp(0, length),
+        ),
+        _ => (
+            normalize(end.unwrap_or(-length - 1)).clamp(-1, length - 1),
+            normalize(start.unwrap_or(length - 1)).clamp(-1, length - 1),
+        ),
+    };
+
+    let mut index = if step > 0 { lower } else { upper };
+    std::iter::from_fn(move || {
+        let inside = match step {
+            0 => false,
+            1.. => index < upper,
+            _ => lower < index,
+        };
+        let current = index;
+        index += step;
+
+        inside.then_some(current)
+    })
+}
+
+fn test(expression: &Expression, current: &Value, root: &Value) -> bool {
+    match expression {
+        Expression::Or(operands) => operands.iter().any(|e| test(e, current, root)),
+        Expression::And(operands) => operands.iter().all(|e| test(e, current, root)),
+        Expression::Not(operand) => !test(operand, current, root),
+        Expression::Exists(path) => !evaluate(path, current, root).is_empty(),
+        Expression::Compare(left, comparison, right) => {
+            let left = operand(left, current, root);
+            let right = operand(right, current, root);
+
+            match comparison {
+                Comparison::Equal => equal(&left, &right),
+                Comparison::NotEqual => !equal(&left, &right),
+                Comparison::Less => less(&left, &right),
+                Comparison::LessOrEqual => less(&left, &right) || equal(&left, &right),
+                Comparison::Greater => less(&right, &left),
+                Comparison::GreaterOrEqual => less(&right, &left) || equal(&left, &right),
+            }
+        }
+    }
+}
+
+fn evaluate<'v, 'a>(
+    path: &Path,
+    current: &'v Value<'a>,
+    root: &'v Value<'a>,
+) -> Vec<&'v Value<'a>> {
+    let start = if path.relative { current } else { root };
+
+    apply(&path.segments, root, start)
+}
+
+fn operand<'o, 'a>(
+    comparable: &'o Comparable,
+    current: &'o Value<'a>,
+    root: &'o Value<'a>,
+) -> Operand<'o, 'a> {
+    match comparable {
+        Comparable::Number(number) => Operand::Number(number),
+        Comparable::String(string) => Operand::String(Cow::Borrowed(string)),
+        Comparable::Bool(boolean) => Operand::Bool(*boolean),
+        Comparable::Null => Operand::Null,
+        Comparable::Path(path) => match evaluate(path, current, root).first() {
+            None => Operand::Nothing,
+            Some(Value::Number(number)) => Operand::Number(number),
+            Some(Value::String(string)) => Operand::String(unescaped(&string.value)),
+            Some(Value::Bool(boolean)) => Operand::Bool(boolean.value),
+            Some(Value::Null(_)) => Operand::Null,
+            Some(container) => Operand::Container(container),
+        },
+    }
+}
+
+fn equal(left: &Operand, right: &Operand) -> bool {
+    match (left, right) {
+        (Operand::Nothing, Operand::Nothing) | (Operand::Null, Operand::Null) => true,
+        (Operand::Number(a), Operand::Number(b)) => a.compare(b) == Some(Ordering::Equal),
+        (Operand::String(a), Operand::String(b)) => a == b,
+        (Operand::Bool(a), Operand::Bool(b)) => a == b,
+        (Operand::Container(a), Operand::Container(b)) => a.equals(b),
+        _ => false,
+    }
+}
+
+/// Only numbers and strings are ordered, strings by their code points.
+fn less(left: &Operand, right: &Operand) -> bool {
+    match (left, right) {
+        (Operand::Number(a), Operand::Number(b)) => a.compare(b) == Some(Ordering::Less),
+        (Operand::String(a), Operand::String(b)) => a < b,
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod query_tests {
+    use super::*;
+    use crate::nodes::Node;
+    use crate::options::{Compact, Dialect, FormatOptions};
+    use crate::parser::parse;
+
+    const STORE: &str = r#"{"store": {
+        "book": [
+            {"category": "reference", "author": "Nigel Rees", "price": 8.95},
+            {"category": "fiction", "author": "Evelyn Waugh", "price": 12.99},
+            {"category": "fiction", "author": "Herman Melville", "isbn": "0-553-21311-3", "price": 8.99},
+            {"category": "fiction", "author": "J. R. R. Tolkien", "isbn": "0-395-19395-8", "price": 22.99}
+        ],
+        "bicycle": {"color": "red", "price": 399}
+    }}"#;
+
+    const FILTERED: &str = r#"{
+        "a": [3, 5, 1, 2, 4, 6, {"b": "j"}, {"b": "k"}, {"b": {}}, {"b": "kilo"}],
+        "o": {"p": 1, "q": 2, "r": 3, "s": 5, "t": {"u": 6}},
+        "e": "f"
+    }"#;
+
+    fn select(json: &str, query: &str) -> String {
+        let value = parse(json, Dialect::Json).unwrap();
+        let matches: Vec<_> = Query::parse(query)
+            .unwrap()
+            .select(&value)
+            .into_iter()
+            .map(|value| {
+                value
+                    .format_compact(Compact::Tight, &FormatOptions::default())
+                    .unwrap()
+            })
+            .collect();
+
+        matches.join(" ")
+    }
+
+    #[test]
+    fn it_selects_children_and_descendants() {
+        let cases = [
+            (
+                "$.store.book[*].author",
+                r#""Nigel Rees" "Evelyn Waugh" "Herman Melville" "J. R. R. Tolkien""#,
+            ),
+            (
+                "$..author",
+                r#""Nigel Rees" "Evelyn Waugh" "Herman Melville" "J. R. R. Tolkien""#,
+            ),
+            ("$.store..price", "8.95 12.99 8.99 22.99 399"),
+            ("$..book[2].author", r#""Herman Melville""#),
+            ("$..book[-1].price", "22.99"),
+            ("$..book[0,1].price", "8.95 12.99"),
+            ("$..book[:2].price", "8.95 12.99"),
+            ("$..book[?@.isbn].price", "8.99 22.99"),
+            ("$..book[?@.price<10].price", "8.95 8.99"),
+            ("$['store'][\"bicycle\"]['col\\u006fr']", r#""red""#),
+            ("$.store.bicycle.*", r#""red" 399"#),
+            ("$.store.nothing", ""),
+        ];
+
+        for (query, expected) in cases {
+            assert_eq!(expected, select(STORE, query), "{query}");
+        }
+    }
+
+    #[test]
+    fn it_selects_slices() {
+        let letters = r#"["a", "b", "c", "d", "e", "f", "g"]"#;
+        let cases = [
+            ("$[1:3]", r#""b" "c""#),
+            ("$[5:]", r#""f" "g""#),
+            ("$[1:5:2]", r#""b" "d""#),
+            ("$[5:1:-2]", r#""f" "d""#),
+            ("$[::-1]", r#""g" "f" "e" "d" "c" "b" "a""#),
+            ("$[-2:]", r#""f" "g""#),
+            ("$[::0]", ""),
+        ];
+
+        for (query, expected) in cases {
+            assert_eq!(expected, select(letters, query), "{query}");
+        }
+    }
+
+    #[test]
+    fn it_filters_with_comparisons_and_logical_operators() {
+        let cases = [
+            ("$.a[?@.b == 'kilo']", r#"{"b":"kilo"}"#),
+            ("$.a[?(@.b == 'kilo')]", r#"{"b":"kilo"}"#),
+            ("$.a[?@>3.5]", "5 4 6"),
+            ("$.a[?@.b]", r#"{"b":"j"} {"b":"k"} {"b":{}} {"b":"kilo"}"#),
+            (
+                "$[?@.*]",
+                r#"[3,5,1,2,4,6,{"b":"j"},{"b":"k"},{"b":{}},{"b":"kilo"}] {"p":1,"q":2,"r":3,"s":5,"t":{"u":6}}"#,
+            ),
+            (
+                "$[?@[?@.b]]",
+                r#"[3,5,1,2,4,6,{"b":"j"},{"b":"k"},{"b":{}},{"b":"kilo"}]"#,
+            ),
+            ("$.o[?@<3, ?@<3]", "1 2 1 2"),
+            ("$.a[?@<2 || @.b == \"k\"]", r#"1 {"b":"k"}"#),
+            ("$.o[?@>1 && @<4]", "2 3"),
+            ("$.o[?@.u || @.x]", r#"{"u":6}"#),
+            ("$.a[?@.b == $.x]", "3 5 1 2 4 6"),
+            (
+                "$.a[?!(@ > 2)]",
+                r#"1 2 {"b":"j"} {"b":"k"} {"b":{}} {"b":"kilo"}"#,
+            ),
+            ("$.a[?@ == $.a[0]]", "3"),
+            ("$.o[?@ == $.o.t]", r#"{"u":6}"#),
+        ];
+
+        for (query, expected) in cases {
+            assert_eq!(expected, select(FILTERED, query), "{query}");
+        }
+    }
+
+    #[test]
+    fn it_compares_numbers_exactly() {
+        let ids = r#"[{"id": 12345678901234567891, "n": [1e-400]}, {"id": 12345678901234567892, "n": [0]}]"#;
+        let cases = [
+            (
+                "$[?@.id == 12345678901234567892].id",
+                "12345678901234567892",
+            ),
+            ("$[?@.id < 12345678901234567892].id", "12345678901234567891"),
+            ("$[?@.n == $[1].n].id", "12345678901234567892"),
+        ];
+
+        for (query, expected) in cases {
+            assert_eq!(expected, select(ids, query), "{query}");
+        }
+    }
+
+    #[test]
+    fn it_rejects_invalid_queries() {
+        for query in [
+            "a.b",
+            "$.a b",
+            "$[01]",
+            "$[9007199254740992]",
+            "$[-9223372036854775808:]",
+            "$[::-9223372036854775808]",
+            "$['\\ud800']",
+            "$[?@.* == 1]",
+            "$[?@..a == 1]",
+            "$. a",
+            "$[?length(@) == 1]",
+            "$[?@.b == {}]",
+        ] {
+            assert!(Query::parse(query).is_err(), "{query}");
+        }
+    }
+
+    #[test]
+    fn it_reports_function_extensions_as_unsupported() {
+        for query in [
+            "$[?length(@) == 1]",
+            "$[?!match(@.a, 'x(')]",
+            "$[?@ == count(@.*)]",
+        ] {
+            let error = Query::parse(query).unwrap_err().to_string();
+            assert!(error.contains("are not supported"), "{query}: {error}");
+        }
+    }
+}
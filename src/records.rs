@@ -0,0 +1,273 @@
+/*
+jfmt
+Copyright (C) 2024 - Víctor Berga
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <http://www.gnu.org/licenses/>.
+*/
+
+//! Inputs that hold many documents: one per line (NDJSON or JSON Lines),
+//! one after the other, or as an RFC 7464 sequence (`application/json-seq`).
+
+use crate::formatter::{self, Error};
+use crate::options::FormatOptions;
+use crate::parser::parse_stream;
+
+/// Starts each record of a JSON text sequence.
+const RECORD_SEPARATOR: char = '\u{1E}';
+
+/// What to do with a record that cannot be formatted.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+pub enum ErrorPolicy {
+    /// Stop at the record
+    Fail,
+    /// Leave the record out of the output
+    Skip,
+    /// Copy the record to the output as it is
+    Passthrough,
+}
+
+/// Where a record is in its input, numbered from 1.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Position {
+    Line(usize),
+    Value(usize),
+}
+
+/// A record that could not be formatted.
+#[derive(Debug)]
+pub struct RecordError {
+    pub position: Position,
+    pub error: Error,
+}
+
+impl RecordError {
+    /// Names the record in diagnostics about the input called `name`.
+    pub fn location(&self, name: &str) -> String {
+        match self.position {
+            Position::Line(line) => format!("{name}:{line}"),
+            Position::Value(value) => format!("{name}: value {value}"),
+        }
+    }
+}
+
+/// Formats every line of `text` on its own, ending each with the newline of
+/// `options`. Blank lines are dropped. Unless the policy is to fail, lines
+/// with errors are given to `report` and the rest are still formatted.
+pub fn format_lines(
+    text: &str,
+    options: &FormatOptions,
+    policy: ErrorPolicy,
+    mut report: impl FnMut(RecordError),
+) -> Result<String, RecordError> {
+    let mut output = String::with_capacity(text.len());
+
+    for (index, line) in text.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        match formatter::format_with(line, options) {
+            Ok(formatted) => output.push_str(&formatted),
+            Err(error) => {
+                let error = RecordError {
+                    position: Position::Line(index + 1),
+                    error: error.on_line(index + 1),
+                };
+                match policy {
+                    ErrorPolicy::Fail => return Err(error),
+                    ErrorPolicy::Skip => {
+                        report(error);
+                        continue;
+                    }
+                    ErrorPolicy::Passthrough => {
+                        report(error);
+                        output.push_str(line);
+                    }
+                }
+            }
+        }
+        output.push_str(options.newline());
+    }
+
+    Ok(output)
+}
+
+/// Formats JSON values written one after the other, with or without
+/// whitespace between them, ending each with the newline of `options`.
+pub fn format_values(text: &str, options: &FormatOptions) -> Result<String, RecordError> {
+    let values = parse_stream(text).map_err(|(index, error)| RecordError {
+        position: Position::Value(index + 1),
+        error: Error::Parse(error),
+    })?;
+
+    let mut output = String::with_capacity(text.len());
+    for (index, value) in values.into_iter().enumerate() {
+        let formatted = formatter::format_value(value, options).map_err(|error| RecordError {
+            position: Position::Value(index + 1),
+            error: Error::Format(error),
+        })?;
+        output.push_str(&formatted);
+        output.push_str(options.newline());
+    }
+
+    Ok(output)
+}
+
+/// Formats an RFC 7464 JSON text sequence. Every record is written after a
+/// record separator and ends with a line feed, as the RFC asks. Empty
+/// records are dropped, and the others are handled like NDJSON lines.
+pub fn format_sequence(
+    text: &str,
+    options: &FormatOptions,
+    policy: ErrorPolicy,
+    mut report: impl FnMut(RecordError),
+) -> Result<String, RecordError> {
+    let mut output = String::with_capacity(text.len());
+    let mut line = 1;
+    let mut index = 0;
+
+    for record in text.split(RECORD_SEPARATOR) {
+        let indent = record.len() - record.trim_start().len();
+        let first_line = line + record[..indent].matches('\n').count();
+        line += record.matches('\n').count();
+
+        let record = record.trim();
+        if record.is_empty() {
+            continue;
+        }
+        index += 1;
+
+        output.push(RECORD_SEPARATOR);
+        match formatter::format_with(record, options) {
+            Ok(formatted) => output.push_str(&formatted),
+            Err(error) => {
+                let error = RecordError {
+                    position: Position::Value(index),
+                    error: error.on_line(first_line),
+                };
+                match policy {
+                    ErrorPolicy::Fail => return Err(error),
+                    ErrorPolicy::Skip => {
+                        report(error);
+                        output.pop();
+                        continue;
+                    }
+                    ErrorPolicy::Passthrough => {
+                        report(error);
+                        output.push_str(record);
+                    }
+                }
+            }
+        }
+        output.push('\n');
+    }
+
+    Ok(output)
+}
+
+#[cfg(test)]
+mod records_tests {
+    use super::*;
+    use crate::options::Compact;
+
+    const LOG: &str = "{\"level\": \"info\"}\n\n{\"level\":\n[1,2]\r\n";
+
+    fn compact() -> FormatOptions {
+        FormatOptions {
+            compact: Some(Compact::Tight),
+            ..FormatOptions::default()
+        }
+    }
+
+    #[test]
+    fn it_formats_each_line() {
+        let result = format_lines(
+            "{ \"a\" : 1 }\n[1, 2]",
+            &compact(),
+            ErrorPolicy::Fail,
+            |_| {},
+        );
+
+        assert_eq!("{\"a\":1}\n[1,2]\n", result.unwrap());
+    }
+
+    #[test]
+    fn it_fails_at_the_first_invalid_line() {
+        let error = format_lines(LOG, &compact(), ErrorPolicy::Fail, |_| {}).unwrap_err();
+
+        assert_eq!(Position::Line(3), error.position);
+        assert_eq!("log.ndjson:3", error.location("log.ndjson"));
+        assert!(error.error.to_string().contains("3 | {\"level\":"));
+    }
+
+    #[test]
+    fn it_skips_or_passes_through_invalid_lines() {
+        let mut lines = vec![];
+        let skipped = format_lines(LOG, &compact(), ErrorPolicy::Skip, |error| {
+            lines.push(error.position)
+        });
+        assert_eq!("{\"level\":\"info\"}\n[1,2]\n", skipped.unwrap());
+        assert_eq!(vec![Position::Line(3)], lines);
+
+        let passed = format_lines(LOG, &compact(), ErrorPolicy::Passthrough, |_| {});
+        assert_eq!(
+            "{\"level\":\"info\"}\n{\"level\":\n[1,2]\n",
+            passed.unwrap()
+        );
+    }
+
+    #[test]
+    fn it_formats_concatenated_values() {
+        let result = format_values("{\"a\" : 1}[1,\n2]\n\"b\" 3", &compact());
+
+        assert_eq!("{\"a\":1}\n[1,2]\n\"b\"\n3\n", result.unwrap());
+    }
+
+    #[test]
+    fn it_names_the_value_with_an_error() {
+        let error = format_values("{}\n[1, 2]\n{\"a\": }", &compact()).unwrap_err();
+
+        assert_eq!(Position::Value(3), error.position);
+        assert_eq!("data.json: value 3", error.location("data.json"));
+        assert!(error.error.to_string().contains("3 | {\"a\": }"));
+    }
+
+    #[test]
+    fn it_formats_json_text_sequences() {
+        let text = "\u{1E}{ \"a\" : 1 }\n\u{1E}\n\u{1E}[1,\n2]\n";
+        let result = format_sequence(text, &compact(), ErrorPolicy::Fail, |_| {});
+
+        assert_eq!("\u{1E}{\"a\":1}\n\u{1E}[1,2]\n", result.unwrap());
+    }
+
+    #[test]
+    fn it_handles_invalid_records_by_policy() {
+        let text = "\u{1E}1\n\u{1E}\n{\"a\":\n\u{1E}2\n";
+
+        let error = format_sequence(text, &compact(), ErrorPolicy::Fail, |_| {}).unwrap_err();
+        assert_eq!(Position::Value(2), error.position);
+        assert!(error.error.to_string().contains("3 | {\"a\":"));
+
+        let mut values = vec![];
+        let skipped = format_sequence(text, &compact(), ErrorPolicy::Skip, |error| {
+            values.push(error.position)
+        });
+        assert_eq!("\u{1E}1\n\u{1E}2\n", skipped.unwrap());
+        assert_eq!(vec![Position::Value(2)], values);
+
+        let passed = format_sequence(text, &compact(), ErrorPolicy::Passthrough, |_| {});
+        assert_eq!("\u{1E}1\n\u{1E}{\"a\":\n\u{1E}2\n", passed.unwrap());
+    }
+}
@@ -0,0 +1,199 @@
+/*
+jfmt
+Copyright (C) 2024 - Víctor Berga
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <http://www.gnu.org/licenses/>.
+*/
+
+use std::cmp::Ordering;
+
+/// How object keys are reordered before printing.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct KeySort {
+    /// Ordering for keys that are not listed in `priority`. Those keys keep
+    /// their input order when it is `None`.
+    pub ordering: Option<KeyOrdering>,
+    /// Keys printed first, in this order.
+    pub priority: Vec<String>,
+    /// Number of object levels to sort, starting at the outermost objects.
+    /// Every level is sorted when it is `None`.
+    pub depth: Option<usize>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+pub enum KeyOrdering {
+    /// Byte-wise comparison
+    Lexical,
+    /// Runs of digits are compared by their numeric value, so `item2` < `item10`
+    Natural,
+    /// Shorter keys first, ties broken lexically
+    Length,
+}
+
+impl KeySort {
+    /// Whether objects nested `depth` levels deep should be sorted.
+    pub fn applies_at(&self, depth: usize) -> bool {
+        self.depth.is_none_or(|limit| depth < limit)
+    }
+
+    pub fn compare(&self, a: &str, b: &str) -> Ordering {
+        let rank = |key: &str| {
+            self.priority
+                .iter()
+                .position(|priority| priority == key)
+                .unwrap_or(self.priority.len())
+        };
+
+        rank(a).cmp(&rank(b)).then_with(|| match self.ordering {
+            Some(ordering) => ordering.compare(a, b),
+            None => Ordering::Equal,
+        })
+    }
+}
+
+impl KeyOrdering {
+    pub fn compare(&self, a: &str, b: &str) -> Ordering {
+        match self {
+            KeyOrdering::Lexical => a.cmp(b),
+            KeyOrdering::Natural => natural(a, b),
+            KeyOrdering::Length => a.chars().count().cmp(&b.chars().count()).then(a.cmp(b)),
+        }
+    }
+}
+
+fn natural(a: &str, b: &str) -> Ordering {
+    let mut a_chunks = chunks(a);
+    let mut b_chunks = chunks(b);
+
+    loop {
+        let ordering = match (a_chunks.next(), b_chunks.next()) {
+            (None, None) => return a.cmp(b),
+            (None, Some(_)) => return Ordering::Less,
+            (Some(_), None) => return Ordering::Greater,
+            (Some(a), Some(b)) => compare_chunks(a, b),
+        };
+
+        if ordering != Ordering::Equal {
+            return ordering;
+        }
+    }
+}
+
+fn compare_chunks(a: &str, b: &str) -> Ordering {
+    let is_number = |chunk: &str| chunk.starts_with(|c: char| c.is_ascii_digit());
+
+    if is_number(a) && is_number(b) {
+        let a = a.trim_start_matches('0');
+        let b = b.trim_start_matches('0');
+
+        a.len().cmp(&b.len()).then_with(|| a.cmp(b))
+    } else {
+        a.cmp(b)
+    }
+}
+
+/// Splits a key into alternating runs of digits and non-digits.
+fn chunks(key: &str) -> impl Iterator<Item = &str> {
+    let mut rest = key;
+
+    std::iter::from_fn(move || {
+        let first = rest.chars().next()?;
+        let end = rest
+            .find(|c: char| c.is_ascii_digit() != first.is_ascii_digit())
+            .unwrap_or(rest.len());
+        let (chunk, remaining) = rest.split_at(end);
+        rest = remaining;
+
+        Some(chunk)
+    })
+}
+
+#[cfg(test)]
+mod sort_tests {
+    use super::*;
+
+    fn sorted(sort: &KeySort, keys: &[&'static str]) -> Vec<&'static str> {
+        let mut keys = keys.to_vec();
+        keys.sort_by(|a, b| sort.compare(a, b));
+
+        keys
+    }
+
+    #[test]
+    fn it_sorts_keys_lexically() {
+        let sort = KeySort {
+            ordering: Some(KeyOrdering::Lexical),
+            ..KeySort::default()
+        };
+
+        assert_eq!(
+            vec!["B", "a", "item10", "item2"],
+            sorted(&sort, &["item2", "a", "item10", "B"])
+        );
+    }
+
+    #[test]
+    fn it_sorts_keys_naturally() {
+        let sort = KeySort {
+            ordering: Some(KeyOrdering::Natural),
+            ..KeySort::default()
+        };
+
+        assert_eq!(
+            vec!["item", "item2", "item02b", "item10", "item10a", "x1y"],
+            sorted(
+                &sort,
+                &["item10a", "x1y", "item10", "item02b", "item2", "item"]
+            )
+        );
+    }
+
+    #[test]
+    fn it_sorts_keys_by_length() {
+        let sort = KeySort {
+            ordering: Some(KeyOrdering::Length),
+            ..KeySort::default()
+        };
+
+        assert_eq!(
+            vec!["b", "aa", "ab", "aaa"],
+            sorted(&sort, &["aaa", "ab", "b", "aa"])
+        );
+    }
+
+    #[test]
+    fn it_puts_priority_keys_first() {
+        let sort = KeySort {
+            priority: vec!["name".to_string(), "version".to_string()],
+            ..KeySort::default()
+        };
+
+        assert_eq!(
+            vec!["name", "version", "scripts", "author"],
+            sorted(&sort, &["scripts", "version", "author", "name"])
+        );
+    }
+
+    #[test]
+    fn it_limits_the_sorted_depth() {
+        let sort = KeySort {
+            depth: Some(2),
+            ..KeySort::default()
+        };
+
+        assert!(sort.applies_at(1));
+        assert!(!sort.applies_at(2));
+    }
+}
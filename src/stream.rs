@@ -0,0 +1,672 @@
+/*
+jfmt
+Copyright (C) 2024 - Víctor Berga
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <http://www.gnu.org/licenses/>.
+*/
+
+//! Formats JSON from a reader into a writer without holding the document in
+//! memory.
+//!
+//! The tokenizer checks the syntax as it reads, and the printer only keeps
+//! the tokens it needs to decide whether a container fits on one line, which
+//! are never more than `max_width`. Memory grows with the nesting depth and
+//! the longest string, not with the size of the document, and the output is
+//! the same as the one rendered from a parsed tree.
+
+use crate::layout::Doc;
+use crate::nodes::{self, Boolean, FormatError, Null, Number, ToDoc};
+use crate::options::{Compact, FormatOptions};
+use crate::theme;
+use std::collections::VecDeque;
+use std::fmt;
+use std::io::{self, BufRead, BufWriter, Write};
+
+#[derive(Debug)]
+pub enum Error {
+    /// The input could not be read.
+    Read(io::Error),
+    /// The output could not be written.
+    Write(io::Error),
+    /// The input is not valid UTF-8 from this byte on.
+    Encoding(usize),
+    /// The input is not valid JSON at this line and column.
+    Syntax {
+        line: usize,
+        column: usize,
+        expected: &'static str,
+    },
+    Format(FormatError),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Read(error) | Error::Write(error) => write!(f, "{error}"),
+            Error::Encoding(offset) => write!(f, "invalid UTF-8 at byte {offset}"),
+            Error::Syntax {
+                line,
+                column,
+                expected,
+            } => write!(f, "invalid JSON at {line}:{column}, expected {expected}"),
+            Error::Format(error) => write!(f, "{error}"),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+/// Errors of the output side, so an atomic write of the output can report
+/// them.
+impl From<io::Error> for Error {
+    fn from(error: io::Error) -> Self {
+        Error::Write(error)
+    }
+}
+
+/// Formats the JSON document read from `reader` into `writer`, followed by
+/// a newline. Sorting keys and canonical output need the whole document and
+/// are not supported.
+pub fn format(
+    reader: impl BufRead,
+    writer: impl Write,
+    options: &FormatOptions,
+) -> Result<(), Error> {
+    let mut printer = Printer {
+        tokens: Tokenizer::new(reader, options),
+        lookahead: VecDeque::new(),
+        writer: BufWriter::new(writer),
+        options,
+        column: 0,
+        level: 0,
+    };
+
+    printer.run()?;
+    printer
+        .writer
+        .write_all(options.newline().as_bytes())
+        .and_then(|()| printer.writer.flush())
+        .map_err(Error::Write)
+}
+
+enum Token {
+    Open(u8),
+    Close(u8),
+    Comma,
+    Colon,
+    /// A key or a scalar value, painted, with its width in columns.
+    Text(String, usize),
+}
+
+/// What the tokenizer accepts next.
+#[derive(Clone, Copy, PartialEq)]
+enum Expect {
+    Value,
+    ValueOrClose,
+    Key,
+    KeyOrClose,
+    Colon,
+    CommaOrClose,
+    End,
+}
+
+struct Tokenizer<'o, R> {
+    reader: R,
+    options: &'o FormatOptions,
+    /// Opening bracket of every container the input is in.
+    containers: Vec<u8>,
+    expect: Expect,
+    line: usize,
+    column: usize,
+    offset: usize,
+}
+
+impl<'o, R: BufRead> Tokenizer<'o, R> {
+    fn new(reader: R, options: &'o FormatOptions) -> Self {
+        Tokenizer {
+            reader,
+            options,
+            containers: Vec::new(),
+            expect: Expect::Value,
+            line: 1,
+            column: 1,
+            offset: 0,
+        }
+    }
+
+    /// Reads the next token, or `None` once the document is complete.
+    fn next(&mut self) -> Result<Option<Token>, Error> {
+        while matches!(self.peek()?, Some(b' ' | b'\t' | b'\r' | b'\n')) {
+            self.bump()?;
+        }
+
+        let Some(byte) = self.peek()? else {
+            return match self.expect {
+                Expect::End => Ok(None),
+                _ => Err(self.syntax(self.expected())),
+            };
+        };
+
+        let accepts_value = matches!(self.expect, Expect::Value | Expect::ValueOrClose);
+        let token = match byte {
+            b'{' | b'[' if accepts_value => {
+                self.bump()?;
+                self.containers.push(byte);
+                self.expect = match byte {
+                    b'{' => Expect::KeyOrClose,
+                    _ => Expect::ValueOrClose,
+                };
+                Token::Open(byte)
+            }
+            b'}' | b']' if self.closes(byte) => {
+                self.bump()?;
+                self.containers.pop();
+                self.expect = self.after_value();
+                Token::Close(byte)
+            }
+            b',' if self.expect == Expect::CommaOrClose => {
+                self.bump()?;
+                self.expect = match self.containers.last() {
+                    Some(b'{') => Expect::Key,
+                    _ => Expect::Value,
+                };
+                Token::Comma
+            }
+            b':' if self.expect == Expect::Colon => {
+                self.bump()?;
+                self.expect = Expect::Value;
+                Token::Colon
+            }
+            b'"' if matches!(self.expect, Expect::Key | Expect::KeyOrClose) => {
+                let key = format!("\"{}\"", self.string()?);
+                self.expect = Expect::Colon;
+                text(Doc::token(key, theme::Token::Key, self.options))
+            }
+            _ if accepts_value => {
+                let doc = match byte {
+                    b'"' => nodes::String {
+                        value: self.string()?.into(),
+                        ..nodes::String::default()
+                    }
+                    .to_doc(self.options),
+                    b't' | b'f' => Boolean {
+                        value: self.literal()? == "true",
+                        ..Boolean::default()
+                    }
+                    .to_doc(self.options),
+                    b'n' => {
+                        self.literal()?;
+                        Null::default().to_doc(self.options)
+                    }
+                    b'-' | b'0'..=b'9' => Number {
+                        lexeme: self.number()?.into(),
+                        ..Number::default()
+                    }
+                    .to_doc(self.options),
+                    _ => return Err(self.syntax(self.expected())),
+                };
+                self.expect = self.after_value();
+                text(doc.map_err(Error::Format)?)
+            }
+            _ => return Err(self.syntax(self.expected())),
+        };
+
+        Ok(Some(token))
+    }
+
+    fn closes(&self, bracket: u8) -> bool {
+        match self.expect {
+            Expect::KeyOrClose => bracket == b'}',
+            Expect::ValueOrClose => bracket == b']',
+            Expect::CommaOrClose => matches!(
+                (self.containers.last(), bracket),
+                (Some(b'{'), b'}') | (Some(b'['), b']')
+            ),
+            _ => false,
+        }
+    }
+
+    fn after_value(&self) -> Expect {
+        match self.containers.last() {
+            Some(_) => Expect::CommaOrClose,
+            None => Expect::End,
+        }
+    }
+
+    fn expected(&self) -> &'static str {
+        match self.expect {
+            Expect::Value => "a value",
+            Expect::ValueOrClose => "a value or ']'",
+            Expect::Key => "a string",
+            Expect::KeyOrClose => "a string or '}'",
+            Expect::Colon => "':'",
+            Expect::CommaOrClose if self.containers.last() == Some(&b'{') => "',' or '}'",
+            Expect::CommaOrClose => "',' or ']'",
+            Expect::End => "the end of the input",
+        }
+    }
+
+    /// Reads a string and returns what is between its quotes, with its
+    /// escapes as written.
+    fn string(&mut self) -> Result<String, Error> {
+        self.bump()?;
+        let start = self.offset;
+        let mut bytes = Vec::new();
+
+        loop {
+            match self.peek()? {
+                Some(b'"') => break,
+                Some(b'\\') => {
+                    bytes.push(self.bump()?);
+                    match self.peek()? {
+                        Some(b'"' | b'\\' | b'/' | b'b' | b'f' | b'n' | b'r' | b't') => {
+                            bytes.push(self.bump()?)
+                        }
+                        Some(b'u') => {
+                            bytes.push(self.bump()?);
+                            for _ in 0..4 {
+                                match self.peek()? {
+                                    Some(digit) if digit.is_ascii_hexdigit() => {
+                                        bytes.push(self.bump()?)
+                                    }
+                                    _ => return Err(self.syntax("a hexadecimal digit")),
+                                }
+                            }
+                        }
+                        _ => return Err(self.syntax("an escape sequence")),
+                    }
+                }
+                Some(byte) if byte >= b' ' => bytes.push(self.bump()?),
+                _ => return Err(self.syntax("a character or '\"'")),
+            }
+        }
+        self.bump()?;
+
+        String::from_utf8(bytes)
+            .map_err(|error| Error::Encoding(start + error.utf8_error().valid_up_to()))
+    }
+
+    fn literal(&mut self) -> Result<&'static str, Error> {
+        let word = match self.peek()? {
+            Some(b't') => "true",
+            Some(b'f') => "false",
+            _ => "null",
+        };
+
+        for expected in word.bytes() {
+            if self.peek()? != Some(expected) {
+                return Err(self.syntax(self.expected()));
+            }
+            self.bump()?;
+        }
+
+        Ok(word)
+    }
+
+    fn number(&mut self) -> Result<String, Error> {
+        let mut lexeme = String::new();
+
+        if self.peek()? == Some(b'-') {
+            lexeme.push(self.bump()? as char);
+        }
+        match self.peek()? {
+            Some(b'0') => lexeme.push(self.bump()? as char),
+            Some(b'1'..=b'9') => self.digits(&mut lexeme)?,
+            _ => return Err(self.syntax("a digit")),
+        }
+        if self.peek()? == Some(b'.') {
+            lexeme.push(self.bump()? as char);
+            self.digits(&mut lexeme)?;
+        }
+        if let Some(b'e' | b'E') = self.peek()? {
+            lexeme.push(self.bump()? as char);
+            if let Some(b'+' | b'-') = self.peek()? {
+                lexeme.push(self.bump()? as char);
+            }
+            if !self.peek()?.is_some_and(|byte| byte.is_ascii_digit()) {
+                return Err(self.syntax("a digit"));
+            }
+            self.digits(&mut lexeme)?;
+        }
+
+        Ok(lexeme)
+    }
+
+    fn digits(&mut self, lexeme: &mut String) -> Result<(), Error> {
+        while self.peek()?.is_some_and(|byte| byte.is_ascii_digit()) {
+            lexeme.push(self.bump()? as char);
+        }
+
+        Ok(())
+    }
+
+    fn peek(&mut self) -> Result<Option<u8>, Error> {
+        loop {
+            match self.reader.fill_buf() {
+                Ok(buffer) => return Ok(buffer.first().copied()),
+                Err(error) if error.kind() == io::ErrorKind::Interrupted => continue,
+                Err(error) => return Err(Error::Read(error)),
+            }
+        }
+    }
+
+    /// Consumes the next byte, which must have been peeked.
+    fn bump(&mut self) -> Result<u8, Error> {
+        let byte = self.peek()?.expect("a byte was peeked");
+        self.reader.consume(1);
+        self.offset += 1;
+
+        if byte == b'\n' {
+            self.line += 1;
+            self.column = 1;
+        } else if byte & 0xC0 != 0x80 {
+            self.column += 1;
+        }
+
+        Ok(byte)
+    }
+
+    fn syntax(&self, expected: &'static str) -> Error {
+        Error::Syntax {
+            line: self.line,
+            column: self.column,
+            expected,
+        }
+    }
+}
+
+fn text(doc: Doc) -> Token {
+    match doc {
+        Doc::Text(text, width) => Token::Text(text, width),
+        _ => unreachable!("keys and scalars render as text"),
+    }
+}
+
+struct Printer<'o, R, W: Write> {
+    tokens: Tokenizer<'o, R>,
+    /// Tokens read ahead to decide whether a container fits on one line.
+    lookahead: VecDeque<Token>,
+    writer: BufWriter<W>,
+    options: &'o FormatOptions,
+    column: usize,
+    level: usize,
+}
+
+impl<R: BufRead, W: Write> Printer<'_, R, W> {
+    fn run(&mut self) -> Result<(), Error> {
+        while let Some(token) = self.next()? {
+            match token {
+                Token::Open(bracket) => self.open(bracket)?,
+                Token::Close(bracket) => {
+                    self.level -= 1;
+                    self.line_break()?;
+                    self.punctuation(bracket_str(bracket))?;
+                }
+                Token::Comma => {
+                    self.punctuation(",")?;
+                    self.line_break()?;
+                }
+                Token::Colon => {
+                    self.punctuation(":")?;
+                    self.write(" ", 1)?;
+                }
+                Token::Text(text, width) => self.write(&text, width)?,
+            }
+        }
+
+        Ok(())
+    }
+
+    fn open(&mut self, bracket: u8) -> Result<(), Error> {
+        if let Some(compact) = self.options.compact {
+            return self.flat(bracket, Some(compact));
+        }
+        if let Some(Token::Close(_)) = self.peek(0)? {
+            self.next()?;
+            return self.empty(bracket);
+        }
+        if self.fits()? {
+            return self.flat(bracket, None);
+        }
+
+        self.punctuation(bracket_str(bracket))?;
+        self.level += 1;
+        self.line_break()
+    }
+
+    /// Prints the container opened by `bracket` on the current line.
+    fn flat(&mut self, bracket: u8, compact: Option<Compact>) -> Result<(), Error> {
+        self.punctuation(bracket_str(bracket))?;
+        let mut depth = 1;
+
+        while depth > 0 {
+            let token = self.next()?.expect("the tokenizer closes every container");
+            match (token, compact) {
+                (Token::Open(bracket), None) if matches!(self.peek(0)?, Some(Token::Close(_))) => {
+                    self.next()?;
+                    self.empty(bracket)?;
+                }
+                (Token::Open(bracket), _) => {
+                    depth += 1;
+                    self.punctuation(bracket_str(bracket))?;
+                }
+                (Token::Close(bracket), _) => {
+                    depth -= 1;
+                    self.punctuation(bracket_str(bracket))?;
+                }
+                (Token::Comma, Some(compact)) => self.punctuation(compact.item_separator())?,
+                (Token::Colon, Some(compact)) => self.punctuation(compact.key_separator())?,
+                (Token::Comma, None) => {
+                    self.punctuation(",")?;
+                    self.write(" ", 1)?;
+                }
+                (Token::Colon, None) => {
+                    self.punctuation(":")?;
+                    self.write(" ", 1)?;
+                }
+                (Token::Text(text, width), _) => self.write(&text, width)?,
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Checks whether the container just opened, followed by its comma if it
+    /// has one, fits in the rest of the line, as the layout of a parsed tree
+    /// does.
+    fn fits(&mut self) -> Result<bool, Error> {
+        let remaining = self.options.max_width as isize - self.column as isize;
+        let mut width = 1;
+        let mut depth = 1;
+        let mut index = 0;
+
+        while width <= remaining {
+            width += match self.peek(index)? {
+                Some(Token::Open(_)) => {
+                    depth += 1;
+                    1
+                }
+                Some(Token::Close(_)) => {
+                    depth -= 1;
+                    1
+                }
+                Some(Token::Comma | Token::Colon) => 2,
+                Some(Token::Text(_, width)) => *width as isize,
+                None => unreachable!("the tokenizer closes every container"),
+            };
+            index += 1;
+
+            if depth == 0 {
+                if let Some(Token::Comma) = self.peek(index)? {
+                    width += 1;
+                }
+                return Ok(width <= remaining);
+            }
+        }
+
+        Ok(false)
+    }
+
+    fn next(&mut self) -> Result<Option<Token>, Error> {
+        match self.lookahead.pop_front() {
+            Some(token) => Ok(Some(token)),
+            None => self.tokens.next(),
+        }
+    }
+
+    fn peek(&mut self, index: usize) -> Result<Option<&Token>, Error> {
+        while self.lookahead.len() <= index {
+            match self.tokens.next()? {
+                Some(token) => self.lookahead.push_back(token),
+                None => return Ok(None),
+            }
+        }
+
+        Ok(self.lookahead.get(index))
+    }
+
+    fn empty(&mut self, bracket: u8) -> Result<(), Error> {
+        let empty = if bracket == b'{' { "{}" } else { "[]" };
+
+        self.punctuation(empty)
+    }
+
+    fn punctuation(&mut self, text: &str) -> Result<(), Error> {
+        let painted = self.options.paint(theme::Token::Punctuation, text);
+
+        self.write(&painted, text.chars().count())
+    }
+
+    fn line_break(&mut self) -> Result<(), Error> {
+        let indentation = self.options.indentation(self.level);
+        self.write(self.options.newline(), 0)?;
+        self.write(&indentation, 0)?;
+        self.column = indentation.chars().count();
+
+        Ok(())
+    }
+
+    fn write(&mut self, text: &str, width: usize) -> Result<(), Error> {
+        self.column += width;
+
+        self.writer.write_all(text.as_bytes()).map_err(Error::Write)
+    }
+}
+
+fn bracket_str(bracket: u8) -> &'static str {
+    match bracket {
+        b'{' => "{",
+        b'}' => "}",
+        b'[' => "[",
+        _ => "]",
+    }
+}
+
+#[cfg(test)]
+mod stream_tests {
+    use super::*;
+    use crate::formatter;
+    use crate::options::{Indent, NumberFormat};
+    use crate::theme::Theme;
+
+    const DOCUMENT: &str = r#"{"name": "jfmt", "tags": ["cli", "json", []], "nested": {"a": {"b": [1, 2.50, -3e2]}, "empty": {}},
+        "long": ["aaaaaaaaaaaaaaaa", "bbbbbbbbbbbbbbbbbbbb", "cccccccccccccccccccccc", "dddddd", "eeeeeeeeeeeeee"],
+        "escapes": "\"é\n", "flags": [true, false, null]}"#;
+
+    fn streamed(json: &str, options: &FormatOptions) -> Result<String, Error> {
+        let mut output = Vec::new();
+        format(json.as_bytes(), &mut output, options)?;
+
+        Ok(String::from_utf8(output).unwrap())
+    }
+
+    fn parsed(json: &str, options: &FormatOptions) -> String {
+        format!("{}\n", formatter::format_with(json, options).unwrap())
+    }
+
+    #[test]
+    fn it_matches_the_formatter_at_every_width() {
+        for max_width in 0..120 {
+            let options = FormatOptions {
+                max_width,
+                ..FormatOptions::default()
+            };
+
+            assert_eq!(
+                parsed(DOCUMENT, &options),
+                streamed(DOCUMENT, &options).unwrap(),
+                "max width {max_width}"
+            );
+        }
+    }
+
+    #[test]
+    fn it_matches_the_formatter_with_other_options() {
+        let options = [
+            FormatOptions {
+                compact: Some(Compact::Tight),
+                ..FormatOptions::default()
+            },
+            FormatOptions {
+                compact: Some(Compact::Spaced),
+                theme: Theme::built_in("default"),
+                ..FormatOptions::default()
+            },
+            FormatOptions {
+                indent: Indent::Tabs,
+                numbers: NumberFormat::Scientific,
+                theme: Theme::built_in("default"),
+                max_width: 40,
+                ..FormatOptions::default()
+            },
+        ];
+
+        for options in options {
+            assert_eq!(
+                parsed(DOCUMENT, &options),
+                streamed(DOCUMENT, &options).unwrap()
+            );
+        }
+        assert_eq!(
+            "\"a\"\n",
+            streamed(" \"a\" ", &FormatOptions::default()).unwrap()
+        );
+    }
+
+    #[test]
+    fn it_reports_where_the_syntax_is_invalid() {
+        let error = |json: &str| {
+            streamed(json, &FormatOptions::default())
+                .unwrap_err()
+                .to_string()
+        };
+
+        assert_eq!("invalid JSON at 2:5, expected ':'", error("{\n\"a\" 1}"));
+        assert_eq!("invalid JSON at 1:4, expected ',' or ']'", error("[1 2]"));
+        assert_eq!("invalid JSON at 1:2, expected a digit", error("-"));
+        assert_eq!(
+            "invalid JSON at 1:4, expected the end of the input",
+            error("{} []")
+        );
+        assert_eq!("invalid JSON at 1:4, expected a value", error("[1,"));
+    }
+
+    #[test]
+    fn it_reports_invalid_utf8() {
+        let mut output = Vec::new();
+        let error = format(&b"[\"\xFF\"]"[..], &mut output, &FormatOptions::default());
+
+        assert!(matches!(error, Err(Error::Encoding(2))));
+    }
+}
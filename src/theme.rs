@@ -0,0 +1,418 @@
+/*
+jfmt
+Copyright (C) 2024 - Víctor Berga
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <http://www.gnu.org/licenses/>.
+*/
+
+use std::fmt;
+use std::path::Path;
+
+/// Styles applied to every kind of token in the output.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Theme {
+    pub key: Style,
+    pub string: Style,
+    pub number: Style,
+    pub boolean: Style,
+    pub null: Style,
+    pub punctuation: Style,
+    pub comment: Style,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Token {
+    Key,
+    String,
+    Number,
+    Boolean,
+    Null,
+    Punctuation,
+    Comment,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Style {
+    pub foreground: Option<Color>,
+    pub bold: bool,
+    pub dim: bool,
+    pub italic: bool,
+    pub underline: bool,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Color {
+    /// One of the 16 basic terminal colors, 8 to 15 being the bright ones.
+    Basic(u8),
+    /// An entry of the 256-color palette.
+    Fixed(u8),
+    Rgb(u8, u8, u8),
+}
+
+/// When to use colors.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+pub enum ColorChoice {
+    /// Only when printing to a terminal, honoring NO_COLOR and CLICOLOR_FORCE
+    Auto,
+    Always,
+    Never,
+}
+
+impl ColorChoice {
+    /// Decides whether to color the output given whether it goes to a
+    /// terminal and a way to read environment variables.
+    pub fn enabled(self, is_terminal: bool, var: impl Fn(&str) -> Option<String>) -> bool {
+        let is_set = |name: &str| var(name).is_some_and(|value| !value.is_empty());
+
+        match self {
+            ColorChoice::Always => true,
+            ColorChoice::Never => false,
+            ColorChoice::Auto if is_set("NO_COLOR") => false,
+            ColorChoice::Auto if is_set("CLICOLOR_FORCE") => {
+                var("CLICOLOR_FORCE").as_deref() != Some("0")
+            }
+            ColorChoice::Auto => is_terminal && var("CLICOLOR").as_deref() != Some("0"),
+        }
+    }
+}
+
+const BASIC_COLORS: [&str; 8] = [
+    "black", "red", "green", "yellow", "blue", "magenta", "cyan", "white",
+];
+
+#[derive(Debug, PartialEq)]
+pub struct ThemeError {
+    line: usize,
+    message: String,
+}
+
+impl fmt::Display for ThemeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl std::error::Error for ThemeError {}
+
+impl Theme {
+    pub const BUILT_IN: [&'static str; 3] = ["default", "solarized", "monochrome-bold"];
+
+    pub fn built_in(name: &str) -> Option<Theme> {
+        match name {
+            "default" => Some(Theme::default_colors()),
+            "solarized" => Some(Theme::solarized()),
+            "monochrome-bold" => Some(Theme::monochrome_bold()),
+            _ => None,
+        }
+    }
+
+    fn default_colors() -> Theme {
+        Theme {
+            key: Style::parse("bold bright-magenta").unwrap(),
+            string: Style::parse("green").unwrap(),
+            number: Style::parse("cyan").unwrap(),
+            boolean: Style::parse("yellow").unwrap(),
+            null: Style::parse("bright-black").unwrap(),
+            punctuation: Style::default(),
+            comment: Style::parse("italic bright-black").unwrap(),
+        }
+    }
+
+    fn solarized() -> Theme {
+        Theme {
+            key: Style::parse("bold #268bd2").unwrap(),
+            string: Style::parse("#2aa198").unwrap(),
+            number: Style::parse("#d33682").unwrap(),
+            boolean: Style::parse("#b58900").unwrap(),
+            null: Style::parse("#cb4b16").unwrap(),
+            punctuation: Style::parse("#839496").unwrap(),
+            comment: Style::parse("italic #586e75").unwrap(),
+        }
+    }
+
+    fn monochrome_bold() -> Theme {
+        Theme {
+            key: Style::parse("bold").unwrap(),
+            boolean: Style::parse("bold").unwrap(),
+            null: Style::parse("bold").unwrap(),
+            punctuation: Style::parse("dim").unwrap(),
+            comment: Style::parse("italic").unwrap(),
+            ..Theme::default()
+        }
+    }
+
+    /// Parses a theme made of `token = style` lines, where the style is a
+    /// list of attributes and at most one color:
+    ///
+    /// ```text
+    /// # Lines starting with '#' are comments
+    /// key = bold #ff79c6
+    /// string = green
+    /// number = 208
+    /// punctuation = dim
+    /// ```
+    ///
+    /// Tokens that are not listed are printed without styling.
+    pub fn parse(source: &str) -> Result<Theme, ThemeError> {
+        let mut theme = Theme::default();
+
+        for (index, line) in source.lines().enumerate() {
+            let error = |message: String| ThemeError {
+                line: index + 1,
+                message,
+            };
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let (token, style) = line
+                .split_once('=')
+                .ok_or_else(|| error("expected `token = style`".to_string()))?;
+            let token = match token.trim() {
+                "key" => Token::Key,
+                "string" => Token::String,
+                "number" => Token::Number,
+                "boolean" => Token::Boolean,
+                "null" => Token::Null,
+                "punctuation" => Token::Punctuation,
+                "comment" => Token::Comment,
+                other => return Err(error(format!("unknown token `{other}`"))),
+            };
+
+            *theme.style_mut(token) = Style::parse(style).map_err(error)?;
+        }
+
+        Ok(theme)
+    }
+
+    /// Resolves a `--theme` argument, either a built-in name or a file.
+    pub fn from_arg(arg: &str) -> Result<Theme, String> {
+        if let Some(theme) = Theme::built_in(arg) {
+            return Ok(theme);
+        }
+
+        let path = Path::new(arg);
+        let source = std::fs::read_to_string(path).map_err(|error| {
+            format!(
+                "{arg} is neither a built-in theme ({}) nor a readable file: {error}",
+                Theme::BUILT_IN.join(", ")
+            )
+        })?;
+
+        Theme::parse(&source).map_err(|error| format!("{arg}: {error}"))
+    }
+
+    pub fn style(&self, token: Token) -> &Style {
+        match token {
+            Token::Key => &self.key,
+            Token::String => &self.string,
+            Token::Number => &self.number,
+            Token::Boolean => &self.boolean,
+            Token::Null => &self.null,
+            Token::Punctuation => &self.punctuation,
+            Token::Comment => &self.comment,
+        }
+    }
+
+    fn style_mut(&mut self, token: Token) -> &mut Style {
+        match token {
+            Token::Key => &mut self.key,
+            Token::String => &mut self.string,
+            Token::Number => &mut self.number,
+            Token::Boolean => &mut self.boolean,
+            Token::Null => &mut self.null,
+            Token::Punctuation => &mut self.punctuation,
+            Token::Comment => &mut self.comment,
+        }
+    }
+}
+
+impl Style {
+    /// Parses whitespace separated attributes (`bold`, `dim`, `italic`,
+    /// `underline`) and a color: a name such as `red` or `bright-red`, a
+    /// 256-color palette index, or a `#rrggbb` true color.
+    pub fn parse(source: &str) -> Result<Style, String> {
+        let mut style = Style::default();
+
+        for word in source.split_whitespace() {
+            match word {
+                "bold" => style.bold = true,
+                "dim" => style.dim = true,
+                "italic" => style.italic = true,
+                "underline" => style.underline = true,
+                color => {
+                    if style.foreground.is_some() {
+                        return Err(format!("more than one color in `{}`", source.trim()));
+                    }
+                    style.foreground = Some(Color::parse(color)?);
+                }
+            }
+        }
+
+        Ok(style)
+    }
+
+    pub fn is_plain(&self) -> bool {
+        *self == Style::default()
+    }
+
+    /// Wraps `text` in the ANSI escape sequences for this style.
+    pub fn paint(&self, text: &str) -> String {
+        if self.is_plain() {
+            return text.to_string();
+        }
+
+        let mut codes = Vec::new();
+        for (enabled, code) in [
+            (self.bold, "1"),
+            (self.dim, "2"),
+            (self.italic, "3"),
+            (self.underline, "4"),
+        ] {
+            if enabled {
+                codes.push(code.to_string());
+            }
+        }
+        if let Some(color) = self.foreground {
+            codes.push(color.code());
+        }
+
+        format!("\u{1b}[{}m{text}\u{1b}[0m", codes.join(";"))
+    }
+}
+
+impl Color {
+    fn parse(source: &str) -> Result<Color, String> {
+        let invalid = || format!("invalid color `{source}`");
+
+        if let Some(hex) = source.strip_prefix('#') {
+            if hex.len() != 6 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+                return Err(invalid());
+            }
+            let channel = |index: usize| u8::from_str_radix(&hex[index..index + 2], 16).unwrap();
+
+            return Ok(Color::Rgb(channel(0), channel(2), channel(4)));
+        }
+
+        if let Ok(index) = source.parse::<u8>() {
+            return Ok(Color::Fixed(index));
+        }
+
+        let (name, offset) = match source.strip_prefix("bright-") {
+            Some(name) => (name, 8),
+            None => (source, 0),
+        };
+        let name = if name == "purple" { "magenta" } else { name };
+
+        BASIC_COLORS
+            .iter()
+            .position(|color| *color == name)
+            .map(|index| Color::Basic(index as u8 + offset))
+            .ok_or_else(invalid)
+    }
+
+    fn code(&self) -> String {
+        match self {
+            Color::Basic(index) if *index < 8 => (30 + index).to_string(),
+            Color::Basic(index) => (90 + index - 8).to_string(),
+            Color::Fixed(index) => format!("38;5;{index}"),
+            Color::Rgb(red, green, blue) => format!("38;2;{red};{green};{blue}"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod theme_tests {
+    use super::*;
+
+    #[test]
+    fn it_paints_basic_colors_and_attributes() {
+        let style = Style::parse("bold bright-purple").unwrap();
+
+        assert_eq!("\u{1b}[1;95mkey\u{1b}[0m", style.paint("key"));
+    }
+
+    #[test]
+    fn it_paints_256_and_true_colors() {
+        let fixed = Style::parse("208").unwrap();
+        let rgb = Style::parse("underline #0a0B0c").unwrap();
+
+        assert_eq!("\u{1b}[38;5;208m1\u{1b}[0m", fixed.paint("1"));
+        assert_eq!("\u{1b}[4;38;2;10;11;12m1\u{1b}[0m", rgb.paint("1"));
+    }
+
+    #[test]
+    fn it_leaves_plain_text_untouched() {
+        assert_eq!("null", Style::default().paint("null"));
+    }
+
+    #[test]
+    fn it_parses_a_theme_file() {
+        let source = "# my theme\nkey = bold red\n\nnull = italic 244\n";
+        let theme = Theme::parse(source).unwrap();
+
+        assert_eq!(Some(Color::Basic(1)), theme.key.foreground);
+        assert!(theme.key.bold);
+        assert_eq!(Some(Color::Fixed(244)), theme.null.foreground);
+        assert!(theme.string.is_plain());
+    }
+
+    #[test]
+    fn it_reports_the_line_of_theme_errors() {
+        let error = Theme::parse("key = bold\nstring = greenish\n").unwrap_err();
+
+        assert_eq!("line 2: invalid color `greenish`", error.to_string());
+    }
+
+    #[test]
+    fn it_resolves_built_in_themes() {
+        for name in Theme::BUILT_IN {
+            assert!(Theme::from_arg(name).is_ok());
+        }
+        assert!(Theme::from_arg("no-such-theme").is_err());
+    }
+
+    #[test]
+    fn it_colors_terminals_by_default() {
+        let no_env = |_: &str| None;
+
+        assert!(ColorChoice::Auto.enabled(true, no_env));
+        assert!(!ColorChoice::Auto.enabled(false, no_env));
+        assert!(ColorChoice::Always.enabled(false, no_env));
+        assert!(!ColorChoice::Never.enabled(true, no_env));
+    }
+
+    #[test]
+    fn it_honors_color_environment_variables() {
+        let env = |vars: &'static [(&'static str, &'static str)]| {
+            move |name: &str| {
+                vars.iter()
+                    .find(|(key, _)| *key == name)
+                    .map(|(_, value)| value.to_string())
+            }
+        };
+
+        assert!(!ColorChoice::Auto.enabled(true, env(&[("NO_COLOR", "1")])));
+        assert!(ColorChoice::Auto.enabled(true, env(&[("NO_COLOR", "")])));
+        assert!(ColorChoice::Auto.enabled(false, env(&[("CLICOLOR_FORCE", "1")])));
+        assert!(!ColorChoice::Auto.enabled(false, env(&[("CLICOLOR_FORCE", "0")])));
+        assert!(!ColorChoice::Auto.enabled(true, env(&[("CLICOLOR", "0")])));
+        assert!(
+            !ColorChoice::Auto.enabled(false, env(&[("NO_COLOR", "1"), ("CLICOLOR_FORCE", "1")]))
+        );
+        assert!(ColorChoice::Always.enabled(false, env(&[("NO_COLOR", "1")])));
+    }
+}